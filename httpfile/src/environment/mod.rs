@@ -0,0 +1,154 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::{Error, Resolver, Result};
+
+pub const ENV_FILE: &str = "http-client.env.json";
+pub const PRIVATE_ENV_FILE: &str = "http-client.private.env.json";
+
+/// Variables available in every environment.
+pub(crate) const SHARED: &str = "$shared";
+
+type EnvMap = BTreeMap<String, HashMap<String, String>>;
+
+/// Environments from `http-client.env.json` and `http-client.private.env.json`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Environments {
+    envs: EnvMap,
+}
+
+/// Variables of a single named environment, including shared ones.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Environment {
+    pub name: String,
+    pub variables: HashMap<String, String>,
+}
+
+impl Environments {
+    /// Loads environment files from `dir`, missing files are treated as empty.
+    pub fn load(dir: &Path) -> Result<Environments> {
+        let mut envs = Environments::default();
+        for file in [ENV_FILE, PRIVATE_ENV_FILE] {
+            let path = dir.join(file);
+            let text = match fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(source) => return Err(Error::ReadFile { path, source }),
+            };
+            envs.merge(&text).map_err(|source| Error::InvalidEnvFile {
+                path: path.clone(),
+                source,
+            })?;
+        }
+
+        Ok(envs)
+    }
+
+    /// Merges a JSON environment file on top of already loaded values.
+    pub fn merge(&mut self, json: &str) -> serde_json::Result<()> {
+        let envs: BTreeMap<String, serde_json::Map<String, Value>> = serde_json::from_str(json)?;
+        for (name, vars) in envs {
+            let env = self.envs.entry(name).or_default();
+            for (key, value) in vars {
+                let value = match value {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                env.insert(key, value);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the variables of environment `name`.
+    pub fn insert(&mut self, name: String, variables: HashMap<String, String>) {
+        self.envs.insert(name, variables);
+    }
+
+    /// Formats the environments as the content of an environment file.
+    pub fn to_json(&self) -> String {
+        let envs: BTreeMap<_, BTreeMap<_, _>> = self
+            .envs
+            .iter()
+            .map(|(name, vars)| (name, vars.iter().collect()))
+            .collect();
+        serde_json::to_string_pretty(&envs).expect("strings to serialize")
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.envs
+            .keys()
+            .map(String::as_str)
+            .filter(|name| *name != SHARED)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names().next().is_none()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Environment> {
+        if name == SHARED {
+            return None;
+        }
+        let vars = self.envs.get(name)?;
+        let mut variables = self.envs.get(SHARED).cloned().unwrap_or_default();
+        variables.extend(vars.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+        Some(Environment {
+            name: name.to_string(),
+            variables,
+        })
+    }
+}
+
+/// Resolves names defined in any environment, for checks that don't pick one.
+impl Resolver for Environments {
+    fn resolve(&self, name: &str) -> Option<String> {
+        self.names().find_map(|env| self.get(env)?.resolve(name))
+    }
+}
+
+impl Resolver for Environment {
+    fn resolve(&self, name: &str) -> Option<String> {
+        self.variables.resolve(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_private_and_shared() -> serde_json::Result<()> {
+        let mut envs = Environments::default();
+        envs.merge(
+            r#"{
+                "$shared": {"version": "v1", "host": "localhost"},
+                "dev": {"host": "dev.example.com", "port": 8080},
+                "prod": {"host": "example.com"}
+            }"#,
+        )?;
+        envs.merge(r#"{"dev": {"token": "secret"}}"#)?;
+
+        let dev = envs.get("dev").unwrap();
+
+        assert_eq!(envs.names().collect::<Vec<_>>(), ["dev", "prod"]);
+        assert_eq!(dev.resolve("host").as_deref(), Some("dev.example.com"));
+        assert_eq!(dev.resolve("port").as_deref(), Some("8080"));
+        assert_eq!(dev.resolve("token").as_deref(), Some("secret"));
+        assert_eq!(dev.resolve("version").as_deref(), Some("v1"));
+        assert_eq!(envs.get("prod").unwrap().resolve("token"), None);
+        assert_eq!(envs.get("$shared"), None);
+
+        let mut copy = Environments::default();
+        copy.merge(&envs.to_json())?;
+        assert_eq!(copy, envs);
+
+        Ok(())
+    }
+}
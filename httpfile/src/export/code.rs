@@ -0,0 +1,686 @@
+use std::fmt::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::{Body, Error, HttpRequest, HttpVersion, Method, Multipart};
+
+/// Target of [`to_code`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Language {
+    /// Async reqwest.
+    Rust,
+    /// The requests package.
+    Python,
+    /// `fetch` as in browsers and Node.js.
+    JavaScript,
+    /// net/http from the standard library.
+    Go,
+}
+
+impl Language {
+    pub const ALL: [Language; 4] = [
+        Language::Rust,
+        Language::Python,
+        Language::JavaScript,
+        Language::Go,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::Python => "python",
+            Language::JavaScript => "javascript",
+            Language::Go => "go",
+        }
+    }
+}
+
+impl FromStr for Language {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Language, Error> {
+        match text.to_ascii_lowercase().as_str() {
+            "rust" | "rs" => Ok(Language::Rust),
+            "python" | "py" => Ok(Language::Python),
+            "javascript" | "js" => Ok(Language::JavaScript),
+            "go" | "golang" => Ok(Language::Go),
+            _ => Err(Error::UnknownLanguage(text.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Formats a resolved request as a snippet sending it and printing the response.
+pub fn to_code(req: &HttpRequest, lang: Language) -> String {
+    let mut out = String::new();
+    match lang {
+        Language::Rust => rust(&mut out, req),
+        Language::Python => python(&mut out, req),
+        Language::JavaScript => javascript(&mut out, req),
+        Language::Go => go(&mut out, req),
+    }
+    .expect("writing to a String not to fail");
+    out
+}
+
+/// A `multipart/form-data` field, as the libraries build them.
+enum Field<'a> {
+    Text {
+        name: &'a str,
+        value: String,
+        mime: Option<&'a str>,
+    },
+    File {
+        name: &'a str,
+        path: &'a Path,
+        file_name: String,
+        mime: Option<&'a str>,
+    },
+}
+
+fn fields(multipart: &Multipart) -> Vec<Field<'_>> {
+    multipart
+        .parts
+        .iter()
+        .map(|part| {
+            let name = part.name().unwrap_or_default();
+            match &part.body {
+                Body::File { path, .. } => Field::File {
+                    name,
+                    path,
+                    file_name: part
+                        .file_name()
+                        .map(String::from)
+                        .or_else(|| Some(path.file_name()?.to_string_lossy().into_owned()))
+                        .unwrap_or_default(),
+                    mime: part.header("content-type"),
+                },
+                body => Field::Text {
+                    name,
+                    value: body.to_string(),
+                    mime: part.header("content-type"),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Headers to set by hand, the libraries write the multipart `Content-Type` with their boundary.
+fn headers(req: &HttpRequest) -> impl Iterator<Item = (&str, &str)> {
+    let multipart = matches!(req.body, Body::Multipart(_));
+    req.headers
+        .iter()
+        .filter(move |h| !(multipart && h.name.eq_ignore_ascii_case("content-type")))
+        .map(|h| (h.name.as_str(), h.value.as_str()))
+}
+
+/// A double-quoted string literal, valid in Python, JavaScript and Go.
+fn quote(text: &str) -> String {
+    serde_json::to_string(text).expect("strings to serialize")
+}
+
+fn path_str(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+fn rust(out: &mut String, req: &HttpRequest) -> fmt::Result {
+    let url = format!("{:?}", req.url.as_str());
+    if let Body::Multipart(multipart) = &req.body {
+        writeln!(out, "let form = reqwest::multipart::Form::new()")?;
+        for field in fields(multipart) {
+            match field {
+                Field::Text {
+                    name,
+                    value,
+                    mime: None,
+                } => writeln!(out, "    .text({:?}, {:?})", name, value)?,
+                Field::Text {
+                    name,
+                    value,
+                    mime: Some(mime),
+                } => writeln!(
+                    out,
+                    "    .part({:?}, reqwest::multipart::Part::text({:?}).mime_str({:?})?)",
+                    name, value, mime
+                )?,
+                Field::File {
+                    name,
+                    path,
+                    file_name,
+                    mime,
+                } => {
+                    let bytes = format!("std::fs::read({:?})?", path_str(path));
+                    let mut part = format!(
+                        "reqwest::multipart::Part::bytes({}).file_name({:?})",
+                        bytes, file_name
+                    );
+                    if let Some(mime) = mime {
+                        write!(part, ".mime_str({:?})?", mime)?;
+                    }
+                    writeln!(out, "    .part({:?}, {})", name, part)?;
+                }
+            }
+        }
+        // Ends the chain after its last call.
+        out.pop();
+        out.push_str(";\n");
+    }
+
+    writeln!(out, "let client = reqwest::Client::new();")?;
+    writeln!(out, "let response = client")?;
+    match &req.method {
+        Method::Get => writeln!(out, "    .get({})", url)?,
+        Method::Post => writeln!(out, "    .post({})", url)?,
+        Method::Put => writeln!(out, "    .put({})", url)?,
+        Method::Delete => writeln!(out, "    .delete({})", url)?,
+        Method::Patch => writeln!(out, "    .patch({})", url)?,
+        Method::Head => writeln!(out, "    .head({})", url)?,
+        method => writeln!(
+            out,
+            "    .request(reqwest::Method::from_bytes(b{:?})?, {})",
+            method.as_str(),
+            url
+        )?,
+    }
+    let version = match req.version {
+        HttpVersion::Http10 => Some("HTTP_10"),
+        HttpVersion::Http2 => Some("HTTP_2"),
+        _ => None,
+    };
+    if let Some(version) = version {
+        writeln!(out, "    .version(reqwest::Version::{})", version)?;
+    }
+    for (name, value) in headers(req) {
+        writeln!(out, "    .header({:?}, {:?})", name, value)?;
+    }
+    match &req.body {
+        Body::Text(text) if text.is_empty() => {}
+        Body::Text(text) => writeln!(out, "    .body({:?})", text)?,
+        Body::File { path, .. } => {
+            writeln!(out, "    .body(std::fs::read({:?})?)", path_str(path))?
+        }
+        Body::Multipart(_) => writeln!(out, "    .multipart(form)")?,
+    }
+    writeln!(out, "    .send()")?;
+    writeln!(out, "    .await?;")?;
+    writeln!(out, "println!(\"{{}}\", response.status());")?;
+    write!(out, "println!(\"{{}}\", response.text().await?);")
+}
+
+fn python(out: &mut String, req: &HttpRequest) -> fmt::Result {
+    writeln!(out, "import requests")?;
+    writeln!(out)?;
+    let method = req.method.as_str();
+    if matches!(
+        req.method,
+        Method::Get | Method::Post | Method::Put | Method::Delete | Method::Patch | Method::Head
+    ) {
+        writeln!(out, "response = requests.{}(", method.to_ascii_lowercase())?;
+    } else {
+        writeln!(out, "response = requests.request(")?;
+        writeln!(out, "    {},", quote(method))?;
+    }
+    writeln!(out, "    {},", quote(req.url.as_str()))?;
+
+    let mut headers = headers(req).peekable();
+    if headers.peek().is_some() {
+        writeln!(out, "    headers={{")?;
+        for (name, value) in headers {
+            writeln!(out, "        {}: {},", quote(name), quote(value))?;
+        }
+        writeln!(out, "    }},")?;
+    }
+    match &req.body {
+        Body::Text(text) if text.is_empty() => {}
+        Body::Text(text) => writeln!(out, "    data={},", quote(text))?,
+        Body::File { path, .. } => {
+            writeln!(out, "    data=open({}, \"rb\"),", quote(&path_str(path)))?
+        }
+        Body::Multipart(multipart) => {
+            let (mut data, mut files) = (Vec::new(), Vec::new());
+            for field in fields(multipart) {
+                match field {
+                    Field::Text {
+                        name,
+                        value,
+                        mime: None,
+                    } => data.push(format!("{}: {}", quote(name), quote(&value))),
+                    // Without a file name requests sends a `files` entry as a plain typed field.
+                    Field::Text {
+                        name,
+                        value,
+                        mime: Some(mime),
+                    } => files.push(format!(
+                        "{}: (None, {}, {})",
+                        quote(name),
+                        quote(&value),
+                        quote(mime)
+                    )),
+                    Field::File {
+                        name,
+                        path,
+                        file_name,
+                        mime,
+                    } => {
+                        let mut file = format!(
+                            "({}, open({}, \"rb\")",
+                            quote(&file_name),
+                            quote(&path_str(path))
+                        );
+                        if let Some(mime) = mime {
+                            write!(file, ", {}", quote(mime))?;
+                        }
+                        files.push(format!("{}: {})", quote(name), file));
+                    }
+                }
+            }
+            for (arg, entries) in [("data", data), ("files", files)] {
+                if !entries.is_empty() {
+                    writeln!(out, "    {}={{", arg)?;
+                    for entry in entries {
+                        writeln!(out, "        {},", entry)?;
+                    }
+                    writeln!(out, "    }},")?;
+                }
+            }
+        }
+    }
+    writeln!(out, ")")?;
+    write!(out, "print(response.status_code, response.text)")
+}
+
+fn javascript(out: &mut String, req: &HttpRequest) -> fmt::Result {
+    let reads_file = match &req.body {
+        Body::File { .. } => true,
+        Body::Multipart(multipart) => multipart
+            .parts
+            .iter()
+            .any(|part| matches!(part.body, Body::File { .. })),
+        Body::Text(_) => false,
+    };
+    if reads_file {
+        writeln!(out, "import {{ readFile }} from \"node:fs/promises\";")?;
+        writeln!(out)?;
+    }
+    if let Body::Multipart(multipart) = &req.body {
+        writeln!(out, "const form = new FormData();")?;
+        for field in fields(multipart) {
+            match field {
+                Field::Text {
+                    name,
+                    value,
+                    mime: None,
+                } => writeln!(out, "form.append({}, {});", quote(name), quote(&value))?,
+                // Only a Blob carries a type, FormData sends it with the `blob` file name.
+                Field::Text {
+                    name,
+                    value,
+                    mime: Some(mime),
+                } => writeln!(
+                    out,
+                    "form.append({}, new Blob([{}], {{ type: {} }}));",
+                    quote(name),
+                    quote(&value),
+                    quote(mime)
+                )?,
+                Field::File {
+                    name,
+                    path,
+                    file_name,
+                    mime,
+                } => {
+                    let options = match mime {
+                        Some(mime) => format!(", {{ type: {} }}", quote(mime)),
+                        None => String::new(),
+                    };
+                    writeln!(
+                        out,
+                        "form.append({}, new Blob([await readFile({})]{}), {});",
+                        quote(name),
+                        quote(&path_str(path)),
+                        options,
+                        quote(&file_name)
+                    )?;
+                }
+            }
+        }
+        writeln!(out)?;
+    }
+
+    writeln!(
+        out,
+        "const response = await fetch({}, {{",
+        quote(req.url.as_str())
+    )?;
+    writeln!(out, "  method: {},", quote(req.method.as_str()))?;
+    let mut headers = headers(req).peekable();
+    if headers.peek().is_some() {
+        writeln!(out, "  headers: {{")?;
+        for (name, value) in headers {
+            writeln!(out, "    {}: {},", quote(name), quote(value))?;
+        }
+        writeln!(out, "  }},")?;
+    }
+    match &req.body {
+        Body::Text(text) if text.is_empty() => {}
+        Body::Text(text) => writeln!(out, "  body: {},", quote(text))?,
+        Body::File { path, .. } => {
+            writeln!(out, "  body: await readFile({}),", quote(&path_str(path)))?
+        }
+        Body::Multipart(_) => writeln!(out, "  body: form,")?,
+    }
+    writeln!(out, "}});")?;
+    write!(out, "console.log(response.status, await response.text());")
+}
+
+fn go(out: &mut String, req: &HttpRequest) -> fmt::Result {
+    let mut imports = vec!["fmt", "io", "net/http"];
+    match &req.body {
+        Body::Text(text) if text.is_empty() => {}
+        Body::Text(_) => imports.push("strings"),
+        Body::File { .. } => imports.push("os"),
+        Body::Multipart(multipart) => {
+            imports.extend(["bytes", "mime/multipart"]);
+            // Go rejects unused imports, `os` only reads the files of file parts.
+            if multipart
+                .parts
+                .iter()
+                .any(|part| matches!(part.body, Body::File { .. }))
+            {
+                imports.push("os");
+            }
+            if multipart
+                .parts
+                .iter()
+                .any(|part| part.header("content-type").is_some())
+            {
+                imports.push("net/textproto");
+            }
+        }
+    }
+    imports.sort_unstable();
+    imports.dedup();
+
+    writeln!(out, "package main")?;
+    writeln!(out)?;
+    writeln!(out, "import (")?;
+    for import in imports {
+        writeln!(out, "\t\"{}\"", import)?;
+    }
+    writeln!(out, ")")?;
+    writeln!(out)?;
+    writeln!(out, "func main() {{")?;
+
+    let check = "\tif err != nil {\n\t\tpanic(err)\n\t}\n";
+    let body = match &req.body {
+        Body::Text(text) if text.is_empty() => "nil",
+        Body::Text(text) => {
+            writeln!(out, "\tbody := strings.NewReader({})", quote(text))?;
+            "body"
+        }
+        Body::File { path, .. } => {
+            writeln!(out, "\tbody, err := os.Open({})", quote(&path_str(path)))?;
+            out.push_str(check);
+            writeln!(out, "\tdefer body.Close()")?;
+            "body"
+        }
+        Body::Multipart(multipart) => {
+            writeln!(out, "\tbody := &bytes.Buffer{{}}")?;
+            writeln!(out, "\tform := multipart.NewWriter(body)")?;
+            for field in fields(multipart) {
+                match field {
+                    Field::Text {
+                        name,
+                        value,
+                        mime: None,
+                    } => writeln!(out, "\tform.WriteField({}, {})", quote(name), quote(&value))?,
+                    Field::Text {
+                        name,
+                        value,
+                        mime: Some(mime),
+                    } => {
+                        writeln!(out, "\t{{")?;
+                        go_part(out, name, None, mime)?;
+                        writeln!(out, "\t\tio.WriteString(part, {})", quote(&value))?;
+                        writeln!(out, "\t}}")?;
+                    }
+                    Field::File {
+                        name,
+                        path,
+                        file_name,
+                        mime,
+                    } => {
+                        writeln!(out, "\t{{")?;
+                        writeln!(
+                            out,
+                            "\t\tdata, err := os.ReadFile({})",
+                            quote(&path_str(path))
+                        )?;
+                        writeln!(out, "\t\tif err != nil {{\n\t\t\tpanic(err)\n\t\t}}")?;
+                        match mime {
+                            Some(mime) => go_part(out, name, Some(&file_name), mime)?,
+                            None => {
+                                writeln!(
+                                    out,
+                                    "\t\tpart, err := form.CreateFormFile({}, {})",
+                                    quote(name),
+                                    quote(&file_name)
+                                )?;
+                                writeln!(out, "\t\tif err != nil {{\n\t\t\tpanic(err)\n\t\t}}")?;
+                            }
+                        }
+                        writeln!(out, "\t\tpart.Write(data)")?;
+                        writeln!(out, "\t}}")?;
+                    }
+                }
+            }
+            writeln!(out, "\tform.Close()")?;
+            "body"
+        }
+    };
+
+    let declare = if matches!(req.body, Body::File { .. }) {
+        "="
+    } else {
+        ":="
+    };
+    writeln!(
+        out,
+        "\treq, err {} http.NewRequest({}, {}, {})",
+        declare,
+        quote(req.method.as_str()),
+        quote(req.url.as_str()),
+        body
+    )?;
+    out.push_str(check);
+    for (name, value) in headers(req) {
+        writeln!(out, "\treq.Header.Add({}, {})", quote(name), quote(value))?;
+    }
+    if let Body::Multipart(_) = req.body {
+        writeln!(
+            out,
+            "\treq.Header.Set(\"Content-Type\", form.FormDataContentType())"
+        )?;
+    }
+    writeln!(out)?;
+    writeln!(out, "\tresp, err := http.DefaultClient.Do(req)")?;
+    out.push_str(check);
+    writeln!(out, "\tdefer resp.Body.Close()")?;
+    writeln!(out, "\tdata, err := io.ReadAll(resp.Body)")?;
+    out.push_str(check);
+    writeln!(out, "\tfmt.Println(resp.Status)")?;
+    writeln!(out, "\tfmt.Println(string(data))")?;
+    write!(out, "}}")
+}
+
+/// Creates `part` with its own `Content-Type`, which `WriteField` and `CreateFormFile` don't take.
+fn go_part(out: &mut String, name: &str, file_name: Option<&str>, mime: &str) -> fmt::Result {
+    // Escaped like Go's multipart writer does.
+    let escape = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut disposition = format!("form-data; name=\"{}\"", escape(name));
+    if let Some(file_name) = file_name {
+        write!(disposition, "; filename=\"{}\"", escape(file_name))?;
+    }
+    writeln!(
+        out,
+        "\t\tpart, err := form.CreatePart(textproto.MIMEHeader{{"
+    )?;
+    writeln!(
+        out,
+        "\t\t\t\"Content-Disposition\": {{{}}},",
+        quote(&disposition)
+    )?;
+    writeln!(out, "\t\t\t\"Content-Type\":        {{{}}},", quote(mime))?;
+    writeln!(out, "\t\t}})")?;
+    writeln!(out, "\t\tif err != nil {{\n\t\t\tpanic(err)\n\t\t}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::{parse, parse_curl, Result};
+
+    fn request() -> Result<HttpRequest> {
+        let input = "POST https://example.com/users HTTP/2
+Content-Type: application/json
+
+{\"name\": \"me\"}
+";
+        Ok(parse(io::Cursor::new(input))?.requests.remove(0))
+    }
+
+    #[test]
+    fn generate_rust() -> Result<()> {
+        assert_eq!(
+            to_code(&request()?, Language::Rust),
+            r#"let client = reqwest::Client::new();
+let response = client
+    .post("https://example.com/users")
+    .version(reqwest::Version::HTTP_2)
+    .header("Content-Type", "application/json")
+    .body("{\"name\": \"me\"}")
+    .send()
+    .await?;
+println!("{}", response.status());
+println!("{}", response.text().await?);"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn generate_python() -> Result<()> {
+        let req = parse_curl("curl -X PROPFIND -F name=John -F 'bio=hi;type=text/markdown' -F 'photo=@./me.png;type=image/png' https://example.com/")?;
+
+        assert_eq!(
+            to_code(&req, Language::Python),
+            r#"import requests
+
+response = requests.request(
+    "PROPFIND",
+    "https://example.com/",
+    data={
+        "name": "John",
+    },
+    files={
+        "bio": (None, "hi", "text/markdown"),
+        "photo": ("me.png", open("./me.png", "rb"), "image/png"),
+    },
+)
+print(response.status_code, response.text)"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn generate_javascript() -> Result<()> {
+        assert_eq!(
+            to_code(&request()?, Language::JavaScript),
+            r#"const response = await fetch("https://example.com/users", {
+  method: "POST",
+  headers: {
+    "Content-Type": "application/json",
+  },
+  body: "{\"name\": \"me\"}",
+});
+console.log(response.status, await response.text());"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn generate_go() -> Result<()> {
+        let code = to_code(&request()?, Language::Go);
+
+        assert!(code.starts_with(
+            "package main
+
+import (
+\t\"fmt\"
+\t\"io\"
+\t\"net/http\"
+\t\"strings\"
+)
+"
+        ));
+        assert!(code.contains("\tbody := strings.NewReader(\"{\\\"name\\\": \\\"me\\\"}\")\n"));
+        assert!(code.contains(
+            "\treq, err := http.NewRequest(\"POST\", \"https://example.com/users\", body)\n"
+        ));
+        assert!(code.contains("\treq.Header.Add(\"Content-Type\", \"application/json\")\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn generate_go_text_form() -> Result<()> {
+        let req = parse_curl("curl -F a=b https://e.com")?;
+
+        let code = to_code(&req, Language::Go);
+
+        assert!(code.starts_with(
+            "package main
+
+import (
+\t\"bytes\"
+\t\"fmt\"
+\t\"io\"
+\t\"mime/multipart\"
+\t\"net/http\"
+)
+"
+        ));
+        assert!(code.contains("\tform.WriteField(\"a\", \"b\")\n"));
+
+        let req = parse_curl("curl -F 'a=b;type=text/markdown' https://e.com")?;
+
+        let code = to_code(&req, Language::Go);
+
+        assert!(code.contains("\t\"net/textproto\"\n"));
+        assert!(code.contains(
+            "\t\tpart, err := form.CreatePart(textproto.MIMEHeader{
+\t\t\t\"Content-Disposition\": {\"form-data; name=\\\"a\\\"\"},
+\t\t\t\"Content-Type\":        {\"text/markdown\"},
+\t\t})
+"
+        ));
+        assert!(code.contains("\t\tio.WriteString(part, \"b\")\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_language() {
+        assert_eq!("JS".parse::<Language>().ok(), Some(Language::JavaScript));
+        assert!("cobol".parse::<Language>().is_err());
+    }
+}
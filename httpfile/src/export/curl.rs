@@ -0,0 +1,183 @@
+use std::fmt::Write;
+
+use crate::{Body, HttpRequest, HttpVersion, Method};
+
+/// Formats a resolved request as a `curl` command for a POSIX shell, one option per line.
+pub fn to_curl(req: &HttpRequest) -> String {
+    let mut args = vec![format!("curl {}", shell_quote(req.url.as_str()))];
+    match &req.method {
+        // With `-X HEAD` curl still waits for a body that never comes.
+        Method::Head => args.push("--head".to_string()),
+        Method::Get if req.body.is_empty() => {}
+        method => args.push(format!("-X {}", shell_quote(method.as_str()))),
+    }
+    let cleartext = req
+        .url
+        .scheme()
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("http"));
+    let version = match req.version {
+        // `--http0.9` only accepts 0.9 responses, curl can't send 0.9 requests.
+        HttpVersion::Http09 => None,
+        HttpVersion::Http10 => Some("--http1.0"),
+        HttpVersion::Http11 => Some("--http1.1"),
+        HttpVersion::Http2 if cleartext => Some("--http2-prior-knowledge"),
+        HttpVersion::Http2 => Some("--http2"),
+        HttpVersion::Http3 => Some("--http3"),
+    };
+    args.extend(version.map(str::to_string));
+
+    let multipart = matches!(req.body, Body::Multipart(_));
+    for h in &req.headers {
+        // curl writes its own boundary for `-F`.
+        if multipart && h.name.eq_ignore_ascii_case("content-type") {
+            continue;
+        }
+        args.push(format!(
+            "-H {}",
+            shell_quote(&format!("{}: {}", h.name, h.value))
+        ));
+    }
+
+    match &req.body {
+        Body::Text(text) if text.is_empty() => {}
+        Body::Text(text) => args.push(format!("--data-raw {}", shell_quote(text))),
+        Body::File { path, .. } => {
+            let arg = format!("@{}", path.display());
+            args.push(format!("--data-binary {}", shell_quote(&arg)));
+        }
+        Body::Multipart(multipart) => {
+            for part in &multipart.parts {
+                let name = part.name().unwrap_or_default();
+                let arg = match &part.body {
+                    Body::File { path, .. } => {
+                        let mut arg = format!("{}=@{}", name, path.display());
+                        if let Some(mime) = part.header("content-type") {
+                            write!(arg, ";type={}", mime).unwrap();
+                        }
+                        match part.file_name() {
+                            Some(file_name) if path.file_name() != Some(file_name.as_ref()) => {
+                                write!(arg, ";filename={}", file_name).unwrap()
+                            }
+                            _ => {}
+                        }
+                        format!("-F {}", shell_quote(&arg))
+                    }
+                    body => match part.header("content-type") {
+                        Some(mime) => {
+                            let arg = format!("{}={};type={}", name, form_value(body), mime);
+                            format!("-F {}", shell_quote(&arg))
+                        }
+                        // Unlike `-F`, `--form-string` doesn't treat `@`, `<` and `;` specially.
+                        None => {
+                            let arg = format!("{}={}", name, body);
+                            format!("--form-string {}", shell_quote(&arg))
+                        }
+                    },
+                };
+                args.push(arg);
+            }
+        }
+    }
+
+    args.join(" \\\n  ")
+}
+
+/// Text value of a `-F` part, double-quoted so that it isn't read from a file or split at `;`.
+fn form_value(body: &Body) -> String {
+    let value = body.to_string();
+    if !value.contains([';', '"']) && !value.starts_with(['@', '<']) {
+        return value;
+    }
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Leaves words made of safe characters as is, single-quotes everything else.
+pub fn shell_quote(word: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c);
+    if !word.is_empty() && word.chars().all(safe) {
+        return word.to_string();
+    }
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::{parse, parse_curl, Result};
+
+    #[test]
+    fn export_request() -> Result<()> {
+        let input = "POST http://localhost:8080/users?name=it's HTTP/2
+Content-Type: application/json
+X-Quote: it's
+
+{\"name\": \"it's me\"}
+";
+        let document = parse(io::Cursor::new(input))?;
+
+        let curl = to_curl(&document.requests[0]);
+
+        assert_eq!(
+            curl,
+            r#"curl 'http://localhost:8080/users?name=it'\''s' \
+  -X POST \
+  --http2-prior-knowledge \
+  -H 'Content-Type: application/json' \
+  -H 'X-Quote: it'\''s' \
+  --data-raw '{"name": "it'\''s me"}'"#
+        );
+        assert_eq!(parse_curl(&curl)?.body, document.requests[0].body);
+
+        let head = to_curl(&parse_curl("curl -I https://example.com/")?);
+        assert_eq!(
+            head,
+            "curl https://example.com/ \\\n  --head \\\n  --http1.1"
+        );
+        assert_eq!(parse_curl(&head)?.method, Method::Head);
+
+        let mut old = parse_curl("curl https://example.com/")?;
+        old.version = HttpVersion::Http09;
+        assert_eq!(to_curl(&old), "curl https://example.com/");
+
+        Ok(())
+    }
+
+    #[test]
+    fn export_multipart() -> Result<()> {
+        let req = parse_curl(
+            "curl -F name=John -F 'bio=hi;type=text/markdown' -F 'photo=@./me.png;type=image/png' \
+             https://example.com/",
+        )?;
+
+        assert_eq!(
+            to_curl(&req),
+            "curl https://example.com/ \\
+  -X POST \\
+  --http1.1 \\
+  --form-string name=John \\
+  -F 'bio=hi;type=text/markdown' \\
+  -F 'photo=@./me.png;type=image/png'"
+        );
+        assert_eq!(parse_curl(&to_curl(&req))?.body, req.body);
+
+        let input = "POST https://example.com/
+Content-Type: multipart/form-data; boundary=b
+
+--b
+Content-Disposition: form-data; name=\"note\"
+Content-Type: text/plain
+
+@a; \"b\"
+--b--
+";
+        let document = parse(io::Cursor::new(input))?;
+
+        assert!(
+            to_curl(&document.requests[0]).ends_with(r#"-F 'note="@a; \"b\"";type=text/plain'"#)
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Value};
+
+use crate::{Body, HttpRequest, Response};
+
+/// A resolved request that was sent and the response it got or why it failed, one HAR entry.
+#[derive(Debug, Clone)]
+pub struct Exchange {
+    pub started: SystemTime,
+    pub request: HttpRequest,
+    pub response: Result<Response, String>,
+    pub timings: Timings,
+}
+
+/// Phases reqwest lets callers measure, connecting and sending the request are part of `wait`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timings {
+    /// From sending the request to receiving the response headers.
+    pub wait: Duration,
+    /// Reading the response body.
+    pub receive: Duration,
+}
+
+/// Formats exchanges as a HAR 1.2 log, as pretty-printed JSON.
+pub fn to_har(exchanges: &[Exchange]) -> String {
+    let entries: Vec<_> = exchanges.iter().map(entry).collect();
+    let har = json!({
+        "log": {
+            "version": "1.2",
+            "creator": {"name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION")},
+            "entries": entries,
+        }
+    });
+    serde_json::to_string_pretty(&har).expect("JSON values to serialize")
+}
+
+fn entry(exchange: &Exchange) -> Value {
+    let Exchange {
+        started,
+        request: req,
+        response: resp,
+        timings,
+    } = exchange;
+    let started: DateTime<Utc> = (*started).into();
+    let (wait, receive) = (millis(timings.wait), millis(timings.receive));
+
+    json!({
+        "startedDateTime": started.to_rfc3339_opts(SecondsFormat::Millis, true),
+        "time": wait + receive,
+        "request": request(req),
+        "response": match resp {
+            Ok(resp) => response(resp),
+            Err(err) => failed(req, err),
+        },
+        "cache": {},
+        "timings": {
+            "blocked": -1,
+            "dns": -1,
+            "connect": -1,
+            "ssl": -1,
+            "send": 0,
+            "wait": wait,
+            "receive": receive,
+        },
+    })
+}
+
+fn response(resp: &Response) -> Value {
+    json!({
+        "status": resp.status,
+        "statusText": "",
+        "httpVersion": resp.version.as_str(),
+        "cookies": [],
+        "headers": resp.headers.iter().map(|(name, value)| pair(name, value)).collect::<Vec<_>>(),
+        "content": {
+            "size": resp.body.len(),
+            "mimeType": resp.header("content-type").unwrap_or_default(),
+            "text": resp.body,
+        },
+        "redirectURL": resp.header("location").unwrap_or_default(),
+        "headersSize": -1,
+        "bodySize": resp.body.len(),
+    })
+}
+
+/// Status 0 with the error, as browsers record requests that got no response.
+fn failed(req: &HttpRequest, err: &str) -> Value {
+    json!({
+        "status": 0,
+        "statusText": "",
+        "httpVersion": req.version.as_str(),
+        "cookies": [],
+        "headers": [],
+        "content": {"size": 0, "mimeType": ""},
+        "redirectURL": "",
+        "headersSize": -1,
+        "bodySize": -1,
+        "comment": err,
+        "_error": err,
+    })
+}
+
+fn request(req: &HttpRequest) -> Value {
+    let headers: Vec<_> = req
+        .headers
+        .iter()
+        .map(|h| pair(&h.name, &h.value))
+        .collect();
+    let query: Vec<_> = req
+        .query()
+        .iter()
+        .map(|(name, value)| pair(name, value))
+        .collect();
+    let mut request = json!({
+        "method": req.method.as_str(),
+        "url": req.url.as_str(),
+        "httpVersion": req.version.as_str(),
+        "cookies": [],
+        "headers": headers,
+        "queryString": query,
+        "headersSize": -1,
+        "bodySize": -1,
+    });
+
+    let mime = req.header("content-type").unwrap_or_default();
+    let post = match &req.body {
+        Body::Text(text) if text.is_empty() => return request,
+        Body::Text(text) => {
+            request["bodySize"] = json!(text.len());
+            json!({"mimeType": mime, "text": text})
+        }
+        Body::File { path, .. } => json!({
+            "mimeType": mime,
+            "text": "",
+            "comment": format!("read from {}", path.display()),
+        }),
+        Body::Multipart(multipart) => {
+            let params: Vec<_> = multipart
+                .parts
+                .iter()
+                .map(|part| {
+                    let mut param = json!({"name": part.name().unwrap_or_default()});
+                    match &part.body {
+                        Body::File { path, .. } => {
+                            let file_name = part
+                                .file_name()
+                                .map(String::from)
+                                .unwrap_or_else(|| path.display().to_string());
+                            param["fileName"] = json!(file_name);
+                        }
+                        body => param["value"] = json!(body.to_string()),
+                    }
+                    if let Some(mime) = part.header("content-type") {
+                        param["contentType"] = json!(mime);
+                    }
+                    param
+                })
+                .collect();
+            json!({"mimeType": mime, "text": "", "params": params})
+        }
+    };
+    request["postData"] = post;
+    request
+}
+
+fn pair(name: &str, value: &str) -> Value {
+    json!({"name": name, "value": value})
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::{import_har, parse, HttpVersion, Result};
+
+    #[test]
+    fn export_round_trip() -> Result<()> {
+        let input = "POST https://example.com/users?page=2 HTTP/2
+Content-Type: application/json
+
+{\"name\": \"me\"}
+";
+        let document = parse(io::Cursor::new(input))?;
+        let exchange = Exchange {
+            started: SystemTime::UNIX_EPOCH + Duration::from_millis(1_654_086_605_250),
+            request: document.requests[0].clone(),
+            response: Ok(Response {
+                version: HttpVersion::Http2,
+                status: 201,
+                headers: vec![("location".into(), "/users/1".into())],
+                body: "{}".into(),
+            }),
+            timings: Timings {
+                wait: Duration::from_millis(120),
+                receive: Duration::from_millis(5),
+            },
+        };
+
+        let failed = Exchange {
+            response: Err("connection refused".into()),
+            timings: Timings {
+                wait: Duration::from_millis(3),
+                receive: Duration::ZERO,
+            },
+            ..exchange.clone()
+        };
+
+        let har = to_har(&[exchange, failed]);
+        let json: Value = serde_json::from_str(&har).unwrap();
+        let entry = &json["log"]["entries"][0];
+
+        assert_eq!(entry["startedDateTime"], "2022-06-01T12:30:05.250Z");
+        assert_eq!(entry["time"], 125.0);
+        assert_eq!(entry["request"]["queryString"][0]["value"], "2");
+        assert_eq!(entry["response"]["redirectURL"], "/users/1");
+        let failed = &json["log"]["entries"][1];
+        assert_eq!(failed["time"], 3.0);
+        assert_eq!(failed["response"]["status"], 0);
+        assert_eq!(failed["response"]["_error"], "connection refused");
+        let (sent, imported) = (&document.requests[0], &import_har(&har)?[0]);
+        assert_eq!(imported.url, sent.url);
+        assert_eq!(imported.version, sent.version);
+        assert_eq!(imported.headers[0].value, sent.headers[0].value);
+        assert_eq!(imported.body, sent.body);
+
+        Ok(())
+    }
+}
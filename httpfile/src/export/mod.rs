@@ -0,0 +1,9 @@
+//! Converters from requests to other tools' formats.
+
+mod code;
+mod curl;
+mod har;
+
+pub use code::{to_code, Language};
+pub use curl::{shell_quote, to_curl};
+pub use har::{to_har, Exchange, Timings};
@@ -0,0 +1,453 @@
+use std::path::PathBuf;
+
+use super::{header, BOUNDARY};
+use crate::parser::query;
+use crate::{Body, Error, Header, HttpRequest, HttpVersion, Method, Multipart, Part, Result};
+
+/// Options that are accepted but don't change the request.
+const IGNORED_FLAGS: [&str; 22] = [
+    "-s",
+    "--silent",
+    "-S",
+    "--show-error",
+    "-k",
+    "--insecure",
+    "-L",
+    "--location",
+    "-v",
+    "--verbose",
+    "-i",
+    "--include",
+    "-f",
+    "--fail",
+    "-N",
+    "--no-buffer",
+    "-#",
+    "--progress-bar",
+    "--compressed",
+    "--globoff",
+    "-g",
+    "--no-keepalive",
+];
+
+/// Options whose argument is skipped.
+const IGNORED_OPTIONS: [&str; 14] = [
+    "-o",
+    "--output",
+    "-m",
+    "--max-time",
+    "--connect-timeout",
+    "-x",
+    "--proxy",
+    "--retry",
+    "-w",
+    "--write-out",
+    "--cacert",
+    "-E",
+    "--cert",
+    "--resolve",
+];
+
+/// Short options that take an argument, used to split `-XPOST` and `-sSL`.
+const SHORT_WITH_ARG: &str = "XHdFuAebomxwE";
+
+/// Parses a `curl` command line as copied from a shell or from browser devtools.
+pub fn parse_curl(command: &str) -> Result<HttpRequest> {
+    let words = split_words(command)?;
+    let mut words = expand_short(words).into_iter();
+    match words.next() {
+        Some(curl) if curl == "curl" => {}
+        _ => return Err(curl_error("expected `curl`")),
+    }
+
+    let mut req = HttpRequest::new();
+    let mut method = None;
+    let mut url = None;
+    let mut data: Vec<String> = Vec::new();
+    let mut data_file = None;
+    let mut parts = Vec::new();
+    let mut get = false;
+
+    while let Some(word) = words.next() {
+        let mut arg = || {
+            words
+                .next()
+                .ok_or_else(|| curl_error(&format!("missing argument of `{}`", word)))
+        };
+        match word.as_str() {
+            "-X" | "--request" => method = Some(arg()?),
+            "-H" | "--header" => req.headers.push(parse_header(&arg()?)?),
+            "-A" | "--user-agent" => req.headers.push(header("User-Agent", &arg()?)),
+            "-e" | "--referer" => req.headers.push(header("Referer", &arg()?)),
+            "-b" | "--cookie" => req.headers.push(header("Cookie", &arg()?)),
+            "-u" | "--user" => {
+                let credentials = base64::encode(arg()?);
+                let value = format!("Basic {}", credentials);
+                req.headers.push(header("Authorization", &value));
+            }
+            "-d" | "--data" | "--data-ascii" | "--data-binary" => {
+                let value = arg()?;
+                match value.strip_prefix('@') {
+                    Some(path) => data_file = Some(PathBuf::from(path)),
+                    None => data.push(value),
+                }
+            }
+            "--data-raw" => data.push(arg()?),
+            "--data-urlencode" => data.push(urlencode(&arg()?)),
+            "-F" | "--form" => parts.push(form_part(&arg()?)?),
+            "--form-string" => parts.push(form_string(&arg()?)?),
+            "-G" | "--get" => get = true,
+            "-I" | "--head" => method = Some("HEAD".to_string()),
+            "--url" => url = Some(arg()?),
+            "--http1.0" => req.version = HttpVersion::Http10,
+            "--http1.1" => req.version = HttpVersion::Http11,
+            "--http2" | "--http2-prior-knowledge" => req.version = HttpVersion::Http2,
+            "--http3" => req.version = HttpVersion::Http3,
+            flag if IGNORED_FLAGS.contains(&flag) => {}
+            option if IGNORED_OPTIONS.contains(&option) => {
+                arg()?;
+            }
+            option if option.starts_with('-') && option.len() > 1 => {
+                return Err(curl_error(&format!("unsupported option `{}`", option)));
+            }
+            _ if url.is_none() => url = Some(word.clone()),
+            _ => return Err(curl_error(&format!("unexpected argument `{}`", word))),
+        }
+    }
+
+    let mut url = url.ok_or_else(|| curl_error("missing URL"))?;
+    if !url.contains("://") {
+        url.insert_str(0, "http://");
+    }
+
+    let has_data = !data.is_empty() || data_file.is_some();
+    if has_data && !parts.is_empty() {
+        return Err(curl_error("`-d` and `-F` can't be combined"));
+    }
+    if get && data_file.is_some() {
+        return Err(curl_error("`-G` can't send a file as the query"));
+    }
+    if get {
+        if !data.is_empty() {
+            url.push(if url.contains('?') { '&' } else { '?' });
+            url.push_str(&data.join("&"));
+        }
+    } else if let Some(path) = data_file {
+        req.body = Body::File {
+            path,
+            substitute: false,
+        };
+    } else if !data.is_empty() {
+        req.body = Body::Text(data.join("&"));
+    }
+    if !parts.is_empty() {
+        req.headers
+            .retain(|h| !h.name.eq_ignore_ascii_case("content-type"));
+        let value = format!("multipart/form-data; boundary={}", BOUNDARY);
+        req.headers.push(header("Content-Type", &value));
+        req.body = Body::Multipart(Multipart {
+            boundary: BOUNDARY.to_string(),
+            parts,
+        });
+    } else if has_data && !get && req.header("content-type").is_none() {
+        let value = "application/x-www-form-urlencoded";
+        req.headers.push(header("Content-Type", value));
+    }
+
+    req.method = match method {
+        Some(method) => method.parse()?,
+        None if get => Method::Get,
+        None if !req.body.is_empty() => Method::Post,
+        None => Method::Get,
+    };
+    req.url = url.parse()?;
+
+    Ok(req)
+}
+
+fn curl_error(message: &str) -> Error {
+    Error::InvalidCurl(message.to_string())
+}
+
+fn parse_header(line: &str) -> Result<Header> {
+    let (name, value) = line
+        .split_once(':')
+        .ok_or_else(|| curl_error(&format!("invalid header `{}`", line)))?;
+    Ok(header(name.trim(), value.trim()))
+}
+
+/// `name=value` with the value encoded, as `--data-urlencode` does.
+fn urlencode(data: &str) -> String {
+    match data.split_once('=') {
+        Some((name, value)) if !name.is_empty() => format!("{}={}", name, query::encode(value)),
+        Some((_, value)) => query::encode(value),
+        None => query::encode(data),
+    }
+}
+
+/// `name=value`, `name=@file` or `name=<file`, optionally followed by `;type=mime`.
+fn form_part(arg: &str) -> Result<Part> {
+    let (name, value) = arg
+        .split_once('=')
+        .ok_or_else(|| curl_error(&format!("invalid form field `{}`", arg)))?;
+    let (value, mime) = match value.split_once(";type=") {
+        Some((value, mime)) => (value, Some(mime)),
+        None => (value, None),
+    };
+
+    let mut disposition = format!("form-data; name=\"{}\"", name);
+    let body = if let Some(path) = value.strip_prefix('@') {
+        let path = PathBuf::from(path);
+        if let Some(file_name) = path.file_name() {
+            disposition.push_str(&format!("; filename=\"{}\"", file_name.to_string_lossy()));
+        }
+        Body::File {
+            path,
+            substitute: false,
+        }
+    } else if let Some(path) = value.strip_prefix('<') {
+        Body::File {
+            path: PathBuf::from(path),
+            substitute: false,
+        }
+    } else {
+        Body::from(value)
+    };
+
+    let mut headers = vec![header("Content-Disposition", &disposition)];
+    if let Some(mime) = mime {
+        headers.push(header("Content-Type", mime));
+    }
+    Ok(Part { headers, body })
+}
+
+/// `name=value` taken literally.
+fn form_string(arg: &str) -> Result<Part> {
+    let (name, value) = arg
+        .split_once('=')
+        .ok_or_else(|| curl_error(&format!("invalid form field `{}`", arg)))?;
+    let disposition = format!("form-data; name=\"{}\"", name);
+    Ok(Part {
+        headers: vec![header("Content-Disposition", &disposition)],
+        body: Body::from(value),
+    })
+}
+
+/// Splits `-XPOST` into `-X POST` and `-sSL` into `-s -S -L`.
+fn expand_short(words: Vec<String>) -> Vec<String> {
+    let mut out = Vec::with_capacity(words.len());
+    for word in words {
+        let short = word.len() > 2 && word.starts_with('-') && !word.starts_with("--");
+        if !short {
+            out.push(word);
+            continue;
+        }
+        for (i, c) in word.char_indices().skip(1) {
+            out.push(format!("-{}", c));
+            if SHORT_WITH_ARG.contains(c) {
+                let rest = &word[i + c.len_utf8()..];
+                if !rest.is_empty() {
+                    out.push(rest.to_string());
+                }
+                break;
+            }
+        }
+    }
+    out
+}
+
+/// Splits a POSIX shell command line into words, like the shell would.
+fn split_words(command: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some('\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                }
+                Some(c) => {
+                    word.push(c);
+                    in_word = true;
+                }
+                None => {}
+            },
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(curl_error("unclosed `'`")),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('\n') => {}
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(curl_error("unclosed `\"`")),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(curl_error("unclosed `\"`")),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_word = true;
+                ansi_c_string(&mut chars, &mut word)?;
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+/// Reads the rest of a `$'...'` string, as written by Chrome's "Copy as cURL".
+fn ansi_c_string(chars: &mut impl Iterator<Item = char>, word: &mut String) -> Result<()> {
+    loop {
+        let c = chars.next().ok_or_else(|| curl_error("unclosed `$'`"))?;
+        match c {
+            '\'' => return Ok(()),
+            '\\' => {
+                let escaped = chars.next().ok_or_else(|| curl_error("unclosed `$'`"))?;
+                match escaped {
+                    'n' => word.push('\n'),
+                    'r' => word.push('\r'),
+                    't' => word.push('\t'),
+                    'x' | 'u' => {
+                        let len = if escaped == 'x' { 2 } else { 4 };
+                        let hex: String = chars.take(len).collect();
+                        let c = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| curl_error(&format!("invalid escape `\\{}`", hex)))?;
+                        word.push(c);
+                    }
+                    c => word.push(c),
+                }
+            }
+            c => word.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_devtools_command() -> Result<()> {
+        let command = r#"curl 'https://example.com/api/users?page=1' \
+  -H 'accept: application/json' \
+  -H "Authorization: Bearer \"token\"" \
+  --data-raw $'{"name":"it\'s me"}' \
+  --compressed"#;
+
+        let req = parse_curl(command)?;
+
+        assert_eq!(req.method, Method::Post);
+        assert_eq!(req.url.as_str(), "https://example.com/api/users?page=1");
+        assert_eq!(req.header("accept"), Some("application/json"));
+        assert_eq!(req.header("authorization"), Some("Bearer \"token\""));
+        assert_eq!(
+            req.header("content-type"),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(req.body, Body::from(r#"{"name":"it's me"}"#));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_options() -> Result<()> {
+        let req = parse_curl("curl -sSL -XPUT -u user:pass --http2 example.com -d a=1 -d b=2")?;
+
+        assert_eq!(req.method, Method::Put);
+        assert_eq!(req.url.as_str(), "http://example.com");
+        assert_eq!(req.version, HttpVersion::Http2);
+        assert_eq!(req.header("authorization"), Some("Basic dXNlcjpwYXNz"));
+        assert_eq!(req.body, Body::from("a=1&b=2"));
+
+        let req = parse_curl("curl -G https://example.com/search --data-urlencode 'q=a b'")?;
+
+        assert_eq!(req.method, Method::Get);
+        assert_eq!(req.url.as_str(), "https://example.com/search?q=a%20b");
+        assert!(req.body.is_empty());
+
+        assert!(matches!(
+            parse_curl("curl --frobnicate https://example.com"),
+            Err(Error::InvalidCurl(_))
+        ));
+        assert!(matches!(
+            parse_curl("wget https://example.com"),
+            Err(Error::InvalidCurl(_))
+        ));
+        assert!(matches!(
+            parse_curl("curl -G -d @query.txt https://example.com"),
+            Err(Error::InvalidCurl(_))
+        ));
+        assert!(matches!(
+            parse_curl("curl -d a=1 -F b=2 https://example.com"),
+            Err(Error::InvalidCurl(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_form() -> Result<()> {
+        let req = parse_curl(
+            "curl -F name=John -F 'photo=@./me.png;type=image/png' https://example.com/",
+        )?;
+
+        let multipart = match &req.body {
+            Body::Multipart(multipart) => multipart,
+            other => panic!("unexpected body {:?}", other),
+        };
+        assert_eq!(req.method, Method::Post);
+        assert_eq!(
+            req.header("content-type"),
+            Some("multipart/form-data; boundary=boundary")
+        );
+        assert_eq!(multipart.parts[0].name(), Some("name"));
+        assert_eq!(multipart.parts[0].body, Body::from("John"));
+        assert_eq!(multipart.parts[1].file_name(), Some("me.png"));
+        assert_eq!(multipart.parts[1].header("content-type"), Some("image/png"));
+        assert_eq!(
+            multipart.parts[1].body,
+            Body::File {
+                path: PathBuf::from("./me.png"),
+                substitute: false,
+            }
+        );
+
+        Ok(())
+    }
+}
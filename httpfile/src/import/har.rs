@@ -0,0 +1,182 @@
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use super::{header, str_field, Format, BOUNDARY};
+use crate::parser::query;
+use crate::{Body, HttpRequest, HttpVersion, Multipart, Part, Result};
+
+/// Converts the requests of a HAR 1.2 log, in the order they were sent.
+pub fn import_har(json: &str) -> Result<Vec<HttpRequest>> {
+    let root: Value =
+        serde_json::from_str(json).map_err(|err| Format::Har.error(err.to_string()))?;
+    let entries = root
+        .pointer("/log/entries")
+        .and_then(Value::as_array)
+        .ok_or_else(|| Format::Har.error("missing `log.entries` list"))?;
+
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let request = entry
+                .get("request")
+                .ok_or_else(|| Format::Har.error(format!("entry {} has no request", i)))?;
+            request_from(request)
+                .map_err(|message| Format::Har.error(format!("entry {}: {}", i, message)))
+        })
+        .collect()
+}
+
+fn request_from(request: &Value) -> std::result::Result<HttpRequest, String> {
+    let mut req = HttpRequest::new();
+
+    let method = str_field(request, "method");
+    req.method = method
+        .parse()
+        .map_err(|_| format!("invalid method `{}`", method))?;
+    let url = str_field(request, "url");
+    req.url = url.parse().map_err(|_| format!("invalid URL `{}`", url))?;
+    req.version = version(str_field(request, "httpVersion"));
+
+    for h in list(request.get("headers")) {
+        let name = str_field(h, "name");
+        // HTTP/2 pseudo-headers are part of the request line, the length is computed on send.
+        if name.starts_with(':') || name.eq_ignore_ascii_case("content-length") {
+            continue;
+        }
+        req.headers.push(header(name, str_field(h, "value")));
+    }
+
+    if let Some(post) = request.get("postData") {
+        let mime = str_field(post, "mimeType");
+        let text = str_field(post, "text");
+        let params = list(post.get("params"));
+        req.body = if !text.is_empty() || params.is_empty() {
+            Body::Text(text.to_string())
+        } else if mime.starts_with("multipart/form-data") {
+            Body::Multipart(Multipart {
+                boundary: BOUNDARY.to_string(),
+                parts: params.iter().map(form_part).collect(),
+            })
+        } else {
+            let pairs: Vec<_> = params
+                .iter()
+                .map(|p| {
+                    let name = query::encode(str_field(p, "name"));
+                    format!("{}={}", name, query::encode(str_field(p, "value")))
+                })
+                .collect();
+            Body::Text(pairs.join("&"))
+        };
+
+        if let Body::Multipart(_) = req.body {
+            req.headers
+                .retain(|h| !h.name.eq_ignore_ascii_case("content-type"));
+            let content_type = format!("multipart/form-data; boundary={}", BOUNDARY);
+            req.headers.push(header("Content-Type", &content_type));
+        } else if !mime.is_empty() && req.header("content-type").is_none() {
+            req.headers.push(header("Content-Type", mime));
+        }
+    }
+
+    Ok(req)
+}
+
+/// Browsers write `http/2.0` or `h3`, anything unknown is sent as HTTP/1.1.
+fn version(text: &str) -> HttpVersion {
+    match text.to_ascii_uppercase().as_str() {
+        "H2" => HttpVersion::Http2,
+        "H3" => HttpVersion::Http3,
+        text => text.parse().unwrap_or_default(),
+    }
+}
+
+fn form_part(param: &Value) -> Part {
+    let name = str_field(param, "name");
+    let mut disposition = format!("form-data; name=\"{}\"", name);
+    let body = match param.get("fileName").and_then(Value::as_str) {
+        Some(file_name) => {
+            disposition.push_str(&format!("; filename=\"{}\"", file_name));
+            Body::File {
+                path: PathBuf::from(file_name),
+                substitute: false,
+            }
+        }
+        None => Body::Text(str_field(param, "value").to_string()),
+    };
+
+    let mut headers = vec![header("Content-Disposition", &disposition)];
+    if let Some(mime) = param.get("contentType").and_then(Value::as_str) {
+        headers.push(header("Content-Type", mime));
+    }
+    Part { headers, body }
+}
+
+fn list(value: Option<&Value>) -> &[Value] {
+    value.and_then(Value::as_array).map_or(&[], Vec::as_slice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, Method};
+
+    #[test]
+    fn import_entries() -> Result<()> {
+        let har = r#"{"log": {"version": "1.2", "entries": [
+            {"request": {
+                "method": "GET", "url": "https://example.com/users?page=2", "httpVersion": "http/2.0",
+                "headers": [{"name": ":authority", "value": "example.com"}, {"name": "Accept", "value": "*/*"}],
+                "queryString": [{"name": "page", "value": "2"}]
+            }},
+            {"request": {
+                "method": "POST", "url": "https://example.com/login", "httpVersion": "HTTP/1.1",
+                "headers": [{"name": "Content-Length", "value": "21"}],
+                "postData": {"mimeType": "application/x-www-form-urlencoded", "params": [
+                    {"name": "user", "value": "me"}, {"name": "remember", "value": "yes please"}
+                ]}
+            }},
+            {"request": {
+                "method": "PUT", "url": "https://example.com/avatar", "httpVersion": "h3",
+                "postData": {"mimeType": "multipart/form-data; boundary=----x", "params": [
+                    {"name": "photo", "fileName": "me.png", "contentType": "image/png"}
+                ]}
+            }}
+        ]}}"#;
+
+        let requests = import_har(har)?;
+
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].version, HttpVersion::Http2);
+        assert_eq!(requests[0].headers.len(), 1);
+        assert_eq!(requests[0].query(), [("page".into(), "2".into())]);
+        assert_eq!(requests[1].method, Method::Post);
+        assert_eq!(
+            requests[1].body,
+            Body::from("user=me&remember=yes%20please")
+        );
+        assert_eq!(
+            requests[1].header("content-type"),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(requests[1].header("content-length"), None);
+        assert_eq!(requests[2].version, HttpVersion::Http3);
+        let part = match &requests[2].body {
+            Body::Multipart(multipart) => &multipart.parts[0],
+            body => panic!("unexpected body {:?}", body),
+        };
+        assert_eq!(part.name(), Some("photo"));
+        assert_eq!(part.header("content-type"), Some("image/png"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn import_invalid() {
+        assert!(matches!(
+            import_har(r#"{"log": {"entries": [{"request": {"method": "GET", "url": "nope"}}]}}"#),
+            Err(Error::InvalidImport { .. })
+        ));
+    }
+}
@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde_json::{Map, Value};
+
+use super::{basic_auth, enabled, header, push_comment, str_field, Collection, Format, BOUNDARY};
+use crate::environment::SHARED;
+use crate::parser::query;
+use crate::{Body, Environments, HttpRequest, Multipart, Part, Result, Url};
+
+/// Converts an Insomnia v4 export, the base environment becomes `$shared`.
+pub fn import_insomnia(json: &str) -> Result<(Collection, Environments)> {
+    let root: Value =
+        serde_json::from_str(json).map_err(|err| Format::Insomnia.error(err.to_string()))?;
+    if root.get("__export_format").and_then(Value::as_u64) != Some(4) {
+        return Err(Format::Insomnia.error("only export format 4 is supported"));
+    }
+    let resources = root
+        .get("resources")
+        .and_then(Value::as_array)
+        .ok_or_else(|| Format::Insomnia.error("missing `resources` list"))?;
+    let of_type = |kind: &'static str| {
+        resources
+            .iter()
+            .filter(move |r| str_field(r, "_type") == kind)
+    };
+
+    let groups: HashMap<_, _> = of_type("request_group")
+        .map(|g| (str_field(g, "_id"), g))
+        .collect();
+    let mut requests: Vec<_> = of_type("request").collect();
+    requests.sort_by(|a, b| sort_key(a).total_cmp(&sort_key(b)));
+
+    let mut collection = Collection::default();
+    for resource in requests {
+        let req = request(resource)?;
+        let title = folder_path(&groups, resource);
+        collection.push(title.as_deref(), req);
+    }
+
+    // Sub-environments are children of the base environment, which belongs to the workspace.
+    let base_ids: Vec<_> = of_type("environment")
+        .filter(|e| str_field(e, "parentId").starts_with("wrk_"))
+        .map(|e| str_field(e, "_id"))
+        .collect();
+    let mut environments = Environments::default();
+    for env in of_type("environment") {
+        let mut variables = HashMap::new();
+        if let Some(data) = env.get("data").and_then(Value::as_object) {
+            flatten("", data, &mut variables);
+        }
+        let name = if base_ids.contains(&str_field(env, "_id")) {
+            SHARED.to_string()
+        } else {
+            str_field(env, "name").to_string()
+        };
+        if !variables.is_empty() {
+            environments.insert(name, variables);
+        }
+    }
+
+    Ok((collection, environments))
+}
+
+fn sort_key(resource: &Value) -> f64 {
+    resource
+        .get("metaSortKey")
+        .and_then(Value::as_f64)
+        .unwrap_or_default()
+}
+
+/// Names of the request groups containing `resource`, outermost first.
+fn folder_path(groups: &HashMap<&str, &Value>, resource: &Value) -> Option<String> {
+    let mut names = Vec::new();
+    let mut parent = str_field(resource, "parentId");
+    while let Some(group) = groups.get(parent) {
+        names.push(str_field(group, "name"));
+        parent = str_field(group, "parentId");
+        // A group can't contain itself, a broken export could loop forever.
+        if names.len() > groups.len() {
+            break;
+        }
+    }
+    names.reverse();
+    (!names.is_empty()).then(|| names.join(" / "))
+}
+
+fn request(resource: &Value) -> Result<HttpRequest> {
+    let mut req = HttpRequest::new();
+    let name = str_field(resource, "name");
+    if !name.is_empty() {
+        req.comment = format!("# {}", name);
+    }
+
+    let method = str_field(resource, "method");
+    req.method = method.parse().map_err(|_| {
+        Format::Insomnia.error(format!("invalid method `{}` of `{}`", method, name))
+    })?;
+    let mut url = template(str_field(resource, "url"));
+    if !url.contains("://") && !url.starts_with("{{") {
+        url.insert_str(0, "http://");
+    }
+    req.url = url
+        .parse::<Url>()
+        .map_err(|_| Format::Insomnia.error(format!("invalid URL `{}` of `{}`", url, name)))?;
+    for param in enabled(resource.get("parameters")) {
+        let (key, value) = pair(param);
+        let param = format!("{}={}", query::encode(&key), query::encode(&value));
+        req.url.push_query(&param);
+    }
+
+    for h in enabled(resource.get("headers")) {
+        let (key, value) = pair(h);
+        if !key.is_empty() {
+            req.headers.push(header(&key, &value));
+        }
+    }
+    if let Some(auth) = resource.get("authentication") {
+        apply_auth(&mut req, auth);
+    }
+    if let Some(body) = resource.get("body") {
+        apply_body(&mut req, body);
+    }
+
+    Ok(req)
+}
+
+fn apply_auth(req: &mut HttpRequest, auth: &Value) {
+    if auth.get("disabled").and_then(Value::as_bool) == Some(true) {
+        return;
+    }
+    let field = |key: &str| template(str_field(auth, key));
+    match str_field(auth, "type") {
+        "" | "none" => {}
+        "bearer" => {
+            let prefix = field("prefix");
+            let prefix = if prefix.is_empty() { "Bearer" } else { &prefix };
+            let value = format!("{} {}", prefix, field("token"));
+            req.headers.push(header("Authorization", &value));
+        }
+        "basic" => basic_auth(req, &field("username"), &field("password")),
+        other => push_comment(req, &format!("# TODO: {} auth isn't supported", other)),
+    }
+}
+
+fn apply_body(req: &mut HttpRequest, body: &Value) {
+    let mime = str_field(body, "mimeType");
+    req.body = match mime {
+        "application/x-www-form-urlencoded" => {
+            let pairs: Vec<_> = enabled(body.get("params"))
+                .map(|p| {
+                    let (key, value) = pair(p);
+                    format!("{}={}", query::encode(&key), query::encode(&value))
+                })
+                .collect();
+            Body::Text(pairs.join("&"))
+        }
+        "multipart/form-data" => Body::Multipart(Multipart {
+            boundary: BOUNDARY.to_string(),
+            parts: enabled(body.get("params")).map(form_part).collect(),
+        }),
+        _ => match body.get("fileName").and_then(Value::as_str) {
+            Some(path) => Body::File {
+                path: PathBuf::from(path),
+                substitute: false,
+            },
+            None => Body::Text(template(str_field(body, "text"))),
+        },
+    };
+
+    let content_type = match mime {
+        "" => return,
+        "multipart/form-data" => {
+            req.headers
+                .retain(|h| !h.name.eq_ignore_ascii_case("content-type"));
+            format!("multipart/form-data; boundary={}", BOUNDARY)
+        }
+        // The text of a GraphQL body is already the JSON payload.
+        "application/graphql" => "application/json".to_string(),
+        mime => mime.to_string(),
+    };
+    if req.header("content-type").is_none() {
+        req.headers.push(header("Content-Type", &content_type));
+    }
+}
+
+fn form_part(param: &Value) -> Part {
+    let (name, value) = pair(param);
+    let mut disposition = format!("form-data; name=\"{}\"", name);
+    let body = if str_field(param, "type") == "file" {
+        let path = PathBuf::from(str_field(param, "fileName"));
+        if let Some(file_name) = path.file_name() {
+            let file_name = file_name.to_string_lossy();
+            disposition.push_str(&format!("; filename=\"{}\"", file_name));
+        }
+        Body::File {
+            path,
+            substitute: false,
+        }
+    } else {
+        Body::Text(value)
+    };
+
+    Part {
+        headers: vec![header("Content-Disposition", &disposition)],
+        body,
+    }
+}
+
+/// Rewrites Nunjucks templates such as `{{ _.name }}` and `{% uuid %}` as placeholders.
+fn template(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        let (close, tag) = match rest[start..].chars().nth(1) {
+            Some('{') => ("}}", false),
+            Some('%') => ("%}", true),
+            _ => {
+                out.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+                continue;
+            }
+        };
+        let end = match rest[start + 2..].find(close) {
+            Some(end) => start + 2 + end,
+            None => break,
+        };
+        out.push_str(&rest[..start]);
+        let inner = rest[start + 2..end].trim();
+        if !tag {
+            let name = inner.strip_prefix("_.").unwrap_or(inner);
+            out.push_str(&format!("{{{{{}}}}}", name));
+        } else {
+            let words: Vec<_> = inner
+                .split_whitespace()
+                .map(|w| w.trim_matches(|c| c == '\'' || c == '"' || c == ','))
+                .collect();
+            match words.as_slice() {
+                ["uuid", ..] => out.push_str("{{$uuid}}"),
+                ["now", "unix"] => out.push_str("{{$timestamp}}"),
+                ["now", "iso-8601"] | ["now"] => out.push_str("{{$datetime iso8601}}"),
+                _ => out.push_str(&rest[start..end + 2]),
+            }
+        }
+        rest = &rest[end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Environment data with nested objects flattened to dotted names.
+fn flatten(prefix: &str, data: &Map<String, Value>, out: &mut HashMap<String, String>) {
+    for (key, value) in data {
+        let name = format!("{}{}", prefix, key);
+        match value {
+            Value::Object(nested) => flatten(&format!("{}.", name), nested, out),
+            Value::String(s) => {
+                out.insert(name, template(s));
+            }
+            other => {
+                out.insert(name, other.to_string());
+            }
+        }
+    }
+}
+
+/// `name` and `value` of a parameter or header, with templates rewritten.
+fn pair(param: &Value) -> (String, String) {
+    (
+        template(str_field(param, "name")),
+        template(str_field(param, "value")),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::{parse, write_collection, Resolver};
+
+    const EXPORT: &str = r#"{
+        "_type": "export",
+        "__export_format": 4,
+        "resources": [
+            {"_id": "req_2", "_type": "request", "parentId": "fld_2", "metaSortKey": -2,
+             "name": "Create user", "method": "POST", "url": "{{ _.base_url }}/users",
+             "headers": [{"name": "Content-Type", "value": "application/json"}],
+             "body": {"mimeType": "application/json", "text": "{\"id\": \"{% uuid 'v4' %}\"}"},
+             "authentication": {"type": "bearer", "token": "{{ _.token }}"}},
+            {"_id": "fld_1", "_type": "request_group", "parentId": "wrk_1", "name": "Admin"},
+            {"_id": "fld_2", "_type": "request_group", "parentId": "fld_1", "name": "Users"},
+            {"_id": "req_1", "_type": "request", "parentId": "wrk_1", "metaSortKey": -3,
+             "name": "Health", "method": "GET", "url": "{{ base_url }}/health",
+             "parameters": [{"name": "verbose", "value": "1"}, {"name": "off", "value": "1", "disabled": true}]},
+            {"_id": "req_3", "_type": "request", "parentId": "fld_2", "metaSortKey": -1,
+             "name": "Log in", "method": "POST", "url": "example.com/login",
+             "body": {"mimeType": "application/x-www-form-urlencoded", "params": [{"name": "user", "value": "{{ _.user }}"}]},
+             "authentication": {"type": "basic", "username": "me", "password": "secret"}},
+            {"_id": "env_1", "_type": "environment", "parentId": "wrk_1", "name": "Base Environment",
+             "data": {"base_url": "http://localhost:8080", "auth": {"user": "me"}}},
+            {"_id": "env_2", "_type": "environment", "parentId": "env_1", "name": "prod",
+             "data": {"base_url": "https://example.com", "token": "{{ _.auth.user }}-token"}}
+        ]
+    }"#;
+
+    #[test]
+    fn import_export() -> Result<()> {
+        let (collection, environments) = import_insomnia(EXPORT)?;
+        let mut out = Vec::new();
+        write_collection(&mut out, &collection)?;
+        let text = String::from_utf8(out).unwrap();
+
+        assert_eq!(
+            text,
+            r#"###
+# Health
+GET {{base_url}}/health?verbose=1 HTTP/1.1
+
+### Admin / Users
+# Create user
+POST {{base_url}}/users HTTP/1.1
+Content-Type: application/json
+Authorization: Bearer {{token}}
+
+{"id": "{{$uuid}}"}
+
+###
+# Log in
+POST http://example.com/login HTTP/1.1
+Authorization: Basic bWU6c2VjcmV0
+Content-Type: application/x-www-form-urlencoded
+
+user={{user}}
+
+"#
+        );
+        assert_eq!(parse(io::Cursor::new(text))?.requests.len(), 3);
+
+        let prod = environments.get("prod").unwrap();
+        assert_eq!(environments.names().collect::<Vec<_>>(), ["prod"]);
+        assert_eq!(
+            prod.resolve("base_url").as_deref(),
+            Some("https://example.com")
+        );
+        assert_eq!(prod.resolve("auth.user").as_deref(), Some("me"));
+        assert_eq!(
+            prod.resolve("token").as_deref(),
+            Some("{{auth.user}}-token")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn rewrite_templates() {
+        assert_eq!(template("{{ _.a }}/{{b}}/{ c }"), "{{a}}/{{b}}/{ c }");
+        assert_eq!(template("{% now 'unix' %}"), "{{$timestamp}}");
+        assert_eq!(
+            template("{% response 'body', 'req_1', '$.id' %}"),
+            "{% response 'body', 'req_1', '$.id' %}"
+        );
+        assert_eq!(template("{{ unclosed"), "{{ unclosed");
+    }
+}
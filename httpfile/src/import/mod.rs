@@ -0,0 +1,116 @@
+//! Converters from other tools' formats to requests.
+
+mod curl;
+mod har;
+mod insomnia;
+mod openapi;
+mod postman;
+
+pub use curl::parse_curl;
+pub use har::import_har;
+pub use insomnia::import_insomnia;
+pub use openapi::import_openapi;
+pub use postman::import_postman;
+
+use serde_json::Value;
+
+use crate::{Error, Header, HttpRequest, Span, Variable};
+
+/// Boundary of the multipart bodies importers create, the parts are written out one by one.
+const BOUNDARY: &str = "boundary";
+
+/// Requests imported from another tool, written with [`write_collection`](crate::write_collection).
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Collection {
+    /// Declared as `@name = value` at the top of the file.
+    pub variables: Vec<Variable>,
+    pub sections: Vec<Section>,
+}
+
+/// Requests of a folder, the title goes after the `###` of the first request.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Section {
+    pub title: Option<String>,
+    pub requests: Vec<HttpRequest>,
+}
+
+impl Collection {
+    /// Adds `req` to the section titled `title`, which is created after the others if needed.
+    fn push(&mut self, title: Option<&str>, req: HttpRequest) {
+        match self
+            .sections
+            .iter_mut()
+            .find(|s| s.title.as_deref() == title)
+        {
+            Some(section) => section.requests.push(req),
+            None => self.sections.push(Section {
+                title: title.map(String::from),
+                requests: vec![req],
+            }),
+        }
+    }
+}
+
+/// Formats read by the importers, named in their errors.
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Har,
+    Postman,
+    Insomnia,
+    OpenApi,
+}
+
+impl Format {
+    fn error(self, message: impl Into<String>) -> Error {
+        let format = match self {
+            Format::Har => "HAR",
+            Format::Postman => "Postman collection",
+            Format::Insomnia => "Insomnia export",
+            Format::OpenApi => "OpenAPI document",
+        };
+        Error::InvalidImport {
+            format,
+            message: message.into(),
+        }
+    }
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header {
+        name: name.to_string(),
+        value: value.to_string(),
+        span: Span::default(),
+    }
+}
+
+/// Adds `Authorization: Basic`, credentials with placeholders can't be encoded ahead of time.
+fn basic_auth(req: &mut HttpRequest, username: &str, password: &str) {
+    let credentials = format!("{}:{}", username, password);
+    let value = if credentials.contains("{{") {
+        push_comment(req, "# TODO: base64-encode the Basic credentials");
+        format!("Basic {}", credentials)
+    } else {
+        format!("Basic {}", base64::encode(credentials))
+    };
+    req.headers.push(header("Authorization", &value));
+}
+
+fn push_comment(req: &mut HttpRequest, line: &str) {
+    if !req.comment.is_empty() {
+        req.comment.push('\n');
+    }
+    req.comment.push_str(line);
+}
+
+/// Entries of a list that aren't `"disabled": true`.
+fn enabled(list: Option<&Value>) -> impl Iterator<Item = &Value> {
+    list.and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|v| v.get("disabled").and_then(Value::as_bool) != Some(true))
+}
+
+/// String value of `key`, empty when it's missing or not a string.
+fn str_field<'a>(value: &'a Value, key: &str) -> &'a str {
+    value.get(key).and_then(Value::as_str).unwrap_or_default()
+}
@@ -0,0 +1,502 @@
+use serde_json::{json, Map, Value};
+
+use super::{header, str_field, Collection, Format};
+use crate::parser::{is_json, query};
+use crate::{Body, HttpRequest, Result, Url, Variable};
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// How deep `$ref`s and nested schemas are followed, recursive schemas stop there.
+const MAX_DEPTH: usize = 8;
+
+/// Generates one named request per operation of an OpenAPI 3.x document, in JSON or YAML.
+pub fn import_openapi(text: &str) -> Result<Collection> {
+    let doc: Value = if text.trim_start().starts_with('{') {
+        serde_json::from_str(text).map_err(|err| Format::OpenApi.error(err.to_string()))?
+    } else {
+        serde_yaml::from_str(text).map_err(|err| Format::OpenApi.error(err.to_string()))?
+    };
+    let version = doc.get("openapi").and_then(Value::as_str);
+    if !version.is_some_and(|v| v.starts_with("3.")) {
+        return Err(Format::OpenApi.error("only OpenAPI 3.x is supported"));
+    }
+    let paths = doc
+        .get("paths")
+        .and_then(Value::as_object)
+        .ok_or_else(|| Format::OpenApi.error("missing `paths`"))?;
+
+    let mut importer = Importer {
+        doc: &doc,
+        collection: Collection::default(),
+    };
+    importer.define("baseUrl", base_url(&doc));
+    for (path, item) in paths {
+        for method in METHODS {
+            if let Some(operation) = item.get(method) {
+                importer.operation(path, method, item, operation)?;
+            }
+        }
+    }
+
+    Ok(importer.collection)
+}
+
+/// URL of the first server with its variables set to their defaults, on `localhost` if relative.
+fn base_url(doc: &Value) -> String {
+    let server = match doc.pointer("/servers/0") {
+        Some(server) => server,
+        None => return "http://localhost".to_string(),
+    };
+    let mut url = str_field(server, "url").to_string();
+    if let Some(variables) = server.get("variables").and_then(Value::as_object) {
+        for (name, var) in variables {
+            url = url.replace(&format!("{{{}}}", name), str_field(var, "default"));
+        }
+    }
+    if url.starts_with("//") {
+        url.insert_str(0, "http:");
+    } else if !url.contains("://") {
+        let path = url.trim_start_matches("./").trim_start_matches('/');
+        url = format!("http://localhost/{}", path);
+    }
+    url.trim_end_matches('/').to_string()
+}
+
+struct Importer<'a> {
+    doc: &'a Value,
+    collection: Collection,
+}
+
+impl<'a> Importer<'a> {
+    /// Declares a variable, a name already declared keeps its first value.
+    fn define(&mut self, name: &str, value: String) {
+        let variables = &mut self.collection.variables;
+        if variables.iter().all(|var| var.name != name) {
+            variables.push(Variable {
+                name: name.to_string(),
+                value,
+                line: 0,
+            });
+        }
+    }
+
+    fn operation(&mut self, path: &str, method: &str, item: &Value, op: &Value) -> Result<()> {
+        let mut req = HttpRequest::new();
+        req.method = method.to_uppercase().parse()?;
+        let name = match op.get("operationId").and_then(Value::as_str) {
+            Some(id) => identifier(id),
+            None => identifier(&format!("{} {}", method, path)),
+        };
+        req.name = Some(name.clone());
+        if let Some(summary) = op.get("summary").and_then(Value::as_str) {
+            req.comment = format!("# {}", summary.lines().next().unwrap_or_default());
+        }
+
+        let mut url = format!("{{{{baseUrl}}}}{}", placeholders(path));
+        let mut query = Vec::new();
+        for param in self.parameters(item, op) {
+            let param_name = str_field(param, "name");
+            // Operations document their own examples, so each one gets its own variables.
+            let var = identifier(&format!("{}_{}", name, param_name));
+            let value = self
+                .param_example(param)
+                .map(|value| match value {
+                    Value::String(s) => s,
+                    value => value.to_string(),
+                })
+                .unwrap_or_default();
+            match str_field(param, "in") {
+                "path" => {
+                    let placeholder = format!("{{{{{}}}}}", param_name);
+                    url = url.replace(&placeholder, &format!("{{{{{}}}}}", var));
+                    self.define(&var, value);
+                }
+                "query" => {
+                    query.push(format!("{}={{{{{}}}}}", query::encode(param_name), var));
+                    self.define(&var, value);
+                }
+                "header" => req.headers.push(header(param_name, &value)),
+                _ => {}
+            }
+        }
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(&query.join("&"));
+        }
+        req.url = url
+            .parse::<Url>()
+            .map_err(|_| Format::OpenApi.error(format!("invalid path `{}`", path)))?;
+
+        if let Some(body) = op.get("requestBody").map(|b| self.deref(b)) {
+            self.body(&mut req, body);
+        }
+
+        let tag = op.pointer("/tags/0").and_then(Value::as_str);
+        self.collection.push(tag, req);
+
+        Ok(())
+    }
+
+    /// Path item parameters overridden by the operation's ones with the same name and location.
+    fn parameters(&self, item: &'a Value, op: &'a Value) -> Vec<&'a Value> {
+        let list = |v: &'a Value| {
+            v.get("parameters")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .map(|p| self.deref(p))
+        };
+        let own: Vec<_> = list(op).collect();
+        let key = |p: &Value| {
+            (
+                str_field(p, "name").to_string(),
+                str_field(p, "in").to_string(),
+            )
+        };
+        let mut params: Vec<_> = list(item)
+            .filter(|p| own.iter().all(|o| key(o) != key(p)))
+            .collect();
+        params.extend(own);
+        params
+    }
+
+    fn param_example(&self, param: &Value) -> Option<Value> {
+        if let Some(example) = param.get("example") {
+            return Some(example.clone());
+        }
+        if let Some(example) = first_example(param) {
+            return Some(self.deref(example).get("value")?.clone());
+        }
+        let schema = self.deref(param.get("schema")?);
+        ["example", "default"]
+            .iter()
+            .find_map(|key| schema.get(*key).cloned())
+            .or_else(|| schema.pointer("/enum/0").cloned())
+    }
+
+    fn body(&self, req: &mut HttpRequest, body: &Value) {
+        let content = match body.get("content").and_then(Value::as_object) {
+            Some(content) if !content.is_empty() => content,
+            _ => return,
+        };
+        let (mime, media) = content
+            .iter()
+            .find(|(mime, _)| is_json(mime))
+            .or_else(|| content.iter().next())
+            .expect("content to not be empty");
+
+        let example = media
+            .get("example")
+            .cloned()
+            .or_else(|| {
+                let example = self.deref(first_example(media)?);
+                example.get("value").cloned()
+            })
+            .or_else(|| Some(self.example(media.get("schema")?, 0)));
+        let text = match (example, mime.as_str()) {
+            (Some(Value::String(text)), _) => text,
+            (Some(example), mime) if is_json(mime) => {
+                serde_json::to_string_pretty(&example).unwrap_or_default()
+            }
+            (Some(Value::Object(fields)), "application/x-www-form-urlencoded") => fields
+                .iter()
+                .map(|(name, value)| {
+                    let value = match value {
+                        Value::String(s) => s.clone(),
+                        value => value.to_string(),
+                    };
+                    format!("{}={}", query::encode(name), query::encode(&value))
+                })
+                .collect::<Vec<_>>()
+                .join("&"),
+            _ => String::new(),
+        };
+
+        req.headers.push(header("Content-Type", mime));
+        req.body = Body::Text(text);
+    }
+
+    /// Example value for `schema`, from its `example`, `default` or generated from its type.
+    fn example(&self, schema: &Value, depth: usize) -> Value {
+        if depth > MAX_DEPTH {
+            return Value::Null;
+        }
+        let schema = self.deref(schema);
+        for key in ["example", "default"] {
+            if let Some(value) = schema.get(key) {
+                return value.clone();
+            }
+        }
+        if let Some(value) = schema.pointer("/enum/0") {
+            return value.clone();
+        }
+        if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+            let mut merged = Map::new();
+            for part in all {
+                match self.example(part, depth + 1) {
+                    Value::Object(fields) => merged.extend(fields),
+                    other => return other,
+                }
+            }
+            return Value::Object(merged);
+        }
+        for key in ["oneOf", "anyOf"] {
+            if let Some(first) = schema.get(key).and_then(|s| s.get(0)) {
+                return self.example(first, depth + 1);
+            }
+        }
+
+        let kind = match schema.get("type") {
+            // OpenAPI 3.1 allows a list of types such as `["string", "null"]`.
+            Some(Value::Array(kinds)) => kinds
+                .iter()
+                .filter_map(Value::as_str)
+                .find(|k| *k != "null")
+                .unwrap_or("null"),
+            Some(kind) => kind.as_str().unwrap_or_default(),
+            None if schema.get("properties").is_some() => "object",
+            None => "",
+        };
+        match kind {
+            "object" => {
+                let fields = schema
+                    .get("properties")
+                    .and_then(Value::as_object)
+                    .into_iter()
+                    .flatten()
+                    .map(|(name, prop)| (name.clone(), self.example(prop, depth + 1)))
+                    .collect();
+                Value::Object(fields)
+            }
+            "array" => match schema.get("items") {
+                Some(items) => json!([self.example(items, depth + 1)]),
+                None => json!([]),
+            },
+            "string" => json!(match str_field(schema, "format") {
+                "date" => "2022-06-01",
+                "date-time" => "2022-06-01T12:30:05Z",
+                "email" => "user@example.com",
+                "uuid" => "3fa85f64-5717-4562-b3fc-2c963f66afa6",
+                "uri" => "https://example.com",
+                _ => "string",
+            }),
+            "integer" | "number" => json!(0),
+            "boolean" => json!(true),
+            _ => Value::Null,
+        }
+    }
+
+    /// Follows local `$ref`s such as `#/components/schemas/User`.
+    fn deref(&self, value: &'a Value) -> &'a Value {
+        let mut value = value;
+        for _ in 0..MAX_DEPTH {
+            let target = value
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|r| r.strip_prefix('#'))
+                .and_then(|pointer| self.doc.pointer(pointer));
+            match target {
+                Some(target) => value = target,
+                None => break,
+            }
+        }
+        value
+    }
+}
+
+fn first_example(value: &Value) -> Option<&Value> {
+    value.get("examples")?.as_object()?.values().next()
+}
+
+/// Turns `/users/{id}` into `/users/{{id}}`.
+fn placeholders(path: &str) -> String {
+    path.replace('{', "{{").replace('}', "}}")
+}
+
+/// Request names can be referenced as `{{name.response...}}`, so they're kept to word characters.
+fn identifier(text: &str) -> String {
+    let mut name = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+            name.push(c);
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    name.trim_end_matches('_').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::{parse, write_collection, Error};
+
+    const SPEC: &str = r#"
+openapi: 3.0.3
+info:
+  title: Users
+  version: "1.0"
+servers:
+  - url: https://{region}.example.com/v1/
+    variables:
+      region:
+        default: eu
+paths:
+  /users:
+    get:
+      tags: [users]
+      summary: List users
+      operationId: listUsers
+      parameters:
+        - name: limit
+          in: query
+          schema:
+            type: integer
+            default: 20
+    post:
+      tags: [users]
+      operationId: createUser
+      requestBody:
+        content:
+          application/json:
+            schema:
+              $ref: '#/components/schemas/User'
+  /users/{id}:
+    parameters:
+      - $ref: '#/components/parameters/Id'
+    delete:
+      tags: [users]
+  /health:
+    get:
+      operationId: health
+components:
+  parameters:
+    Id:
+      name: id
+      in: path
+      required: true
+      example: 42
+  schemas:
+    User:
+      type: object
+      properties:
+        name:
+          type: string
+        email:
+          type: string
+          format: email
+        roles:
+          type: array
+          items:
+            enum: [admin, user]
+"#;
+
+    #[test]
+    fn import_spec() -> Result<()> {
+        let collection = import_openapi(SPEC)?;
+        let mut out = Vec::new();
+        write_collection(&mut out, &collection)?;
+        let text = String::from_utf8(out).unwrap();
+
+        assert_eq!(
+            text,
+            r#"@baseUrl = https://eu.example.com/v1
+@listUsers_limit = 20
+@delete_users_id_id = 42
+
+### users
+# List users
+# @name listUsers
+GET {{baseUrl}}/users?limit={{listUsers_limit}} HTTP/1.1
+
+###
+# @name createUser
+POST {{baseUrl}}/users HTTP/1.1
+Content-Type: application/json
+
+{
+  "name": "string",
+  "email": "user@example.com",
+  "roles": [
+    "admin"
+  ]
+}
+
+###
+# @name delete_users_id
+DELETE {{baseUrl}}/users/{{delete_users_id_id}} HTTP/1.1
+
+###
+# @name health
+GET {{baseUrl}}/health HTTP/1.1
+
+"#
+        );
+        let document = parse(io::Cursor::new(text))?;
+        assert_eq!(document.requests.len(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn import_json_spec() -> Result<()> {
+        let spec = r#"{"openapi": "3.1.0", "paths": {"/ping": {"head": {}}}}"#;
+
+        let collection = import_openapi(spec)?;
+
+        assert_eq!(collection.variables[0].value, "http://localhost");
+        let req = &collection.sections[0].requests[0];
+        assert_eq!(req.name.as_deref(), Some("head_ping"));
+        assert_eq!(req.url.as_str(), "{{baseUrl}}/ping");
+
+        Ok(())
+    }
+
+    #[test]
+    fn import_operation_variables() -> Result<()> {
+        let spec = r#"{
+            "openapi": "3.0.0",
+            "servers": [{"url": "/api/"}],
+            "paths": {
+                "/users": {"get": {"operationId": "users", "parameters": [
+                    {"name": "page", "in": "query", "example": 1}
+                ]}},
+                "/posts": {"get": {"operationId": "posts", "parameters": [
+                    {"name": "page", "in": "query", "example": 5}
+                ]}}
+            }
+        }"#;
+
+        let collection = import_openapi(spec)?;
+
+        let variables: Vec<_> = collection
+            .variables
+            .iter()
+            .map(|var| (var.name.as_str(), var.value.as_str()))
+            .collect();
+        assert_eq!(
+            variables,
+            [
+                ("baseUrl", "http://localhost/api"),
+                ("users_page", "1"),
+                ("posts_page", "5")
+            ]
+        );
+        let req = &collection.sections[0].requests[1];
+        assert_eq!(req.url.as_str(), "{{baseUrl}}/posts?page={{posts_page}}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn import_swagger_2() {
+        assert!(matches!(
+            import_openapi(r#"{"swagger": "2.0", "paths": {}}"#),
+            Err(Error::InvalidImport { .. })
+        ));
+    }
+}
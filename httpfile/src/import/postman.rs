@@ -0,0 +1,408 @@
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+use super::{basic_auth, enabled, header, push_comment, Collection, Format, BOUNDARY};
+use crate::parser::query;
+use crate::{Body, HttpRequest, Multipart, Part, Result, Url, Variable};
+
+/// Converts a Postman Collection v2.1 export, folders become sections.
+pub fn import_postman(json: &str) -> Result<Collection> {
+    let root: Value =
+        serde_json::from_str(json).map_err(|err| Format::Postman.error(err.to_string()))?;
+    let items = root
+        .get("item")
+        .and_then(Value::as_array)
+        .ok_or_else(|| Format::Postman.error("missing `item` list"))?;
+
+    let variables = enabled(root.get("variable"))
+        .filter_map(|var| {
+            Some(Variable {
+                name: var.get("key")?.as_str()?.to_string(),
+                value: value_str(var.get("value")),
+                line: 0,
+            })
+        })
+        .collect();
+    let mut collection = Collection {
+        variables,
+        sections: Vec::new(),
+    };
+    walk(items, None, root.get("auth"), &mut collection)?;
+
+    Ok(collection)
+}
+
+/// Adds requests of `items` and of their folders in order, `auth` is inherited from parents.
+fn walk(
+    items: &[Value],
+    title: Option<&str>,
+    auth: Option<&Value>,
+    collection: &mut Collection,
+) -> Result<()> {
+    for item in items {
+        let name = item.get("name").and_then(Value::as_str).unwrap_or_default();
+        if let Some(children) = item.get("item").and_then(Value::as_array) {
+            let title = match title {
+                Some(parent) => format!("{} / {}", parent, name),
+                None => name.to_string(),
+            };
+            let auth = item.get("auth").or(auth);
+            walk(children, Some(&title), auth, collection)?;
+            continue;
+        }
+
+        let req = request(item, name, auth)?;
+        collection.push(title, req);
+    }
+
+    Ok(())
+}
+
+fn request(item: &Value, name: &str, auth: Option<&Value>) -> Result<HttpRequest> {
+    let mut req = HttpRequest::new();
+    if !name.is_empty() {
+        req.comment = format!("# {}", name);
+    }
+
+    let request = item.get("request").unwrap_or(&Value::Null);
+    // A request may be just its URL.
+    let url = match request {
+        Value::String(url) => url.clone(),
+        request => url(request.get("url")),
+    };
+    req.url = parse_url(url, name)?;
+    let method = request
+        .get("method")
+        .and_then(Value::as_str)
+        .unwrap_or("GET");
+    req.method = method
+        .parse()
+        .map_err(|_| Format::Postman.error(format!("invalid method `{}` of `{}`", method, name)))?;
+
+    for h in enabled(request.get("header")) {
+        if let Some(key) = h.get("key").and_then(Value::as_str) {
+            req.headers.push(header(key, &value_str(h.get("value"))));
+        }
+    }
+    if let Some(auth) = request.get("auth").or(auth) {
+        apply_auth(&mut req, auth);
+    }
+    if let Some(body) = request.get("body") {
+        apply_body(&mut req, body);
+    }
+
+    Ok(req)
+}
+
+/// `raw` when present, otherwise the URL put together from its parts.
+fn url(url: Option<&Value>) -> String {
+    let url = match url {
+        Some(Value::String(url)) => return url.clone(),
+        Some(url) => url,
+        None => return String::new(),
+    };
+    if let Some(raw) = url.get("raw").and_then(Value::as_str) {
+        return raw.to_string();
+    }
+
+    let join = |key: &str, sep: &str| match url.get(key) {
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .map(|p| value_str(Some(p)))
+            .collect::<Vec<_>>()
+            .join(sep),
+        other => value_str(other),
+    };
+    let mut text = String::new();
+    if let Some(protocol) = url.get("protocol").and_then(Value::as_str) {
+        text.push_str(&format!("{}://", protocol));
+    }
+    text.push_str(&join("host", "."));
+    let path = join("path", "/");
+    if !path.is_empty() {
+        text.push('/');
+        text.push_str(&path);
+    }
+    let query: Vec<_> = enabled(url.get("query"))
+        .map(|q| {
+            let key = value_str(q.get("key"));
+            format!(
+                "{}={}",
+                query::encode(&key),
+                query::encode(&value_str(q.get("value")))
+            )
+        })
+        .collect();
+    if !query.is_empty() {
+        text.push('?');
+        text.push_str(&query.join("&"));
+    }
+    text
+}
+
+/// Postman defaults to `http://` when the URL has no scheme.
+fn parse_url(mut url: String, name: &str) -> Result<Url> {
+    if !url.contains("://") && !url.starts_with("{{") {
+        url.insert_str(0, "http://");
+    }
+    url.parse()
+        .map_err(|_| Format::Postman.error(format!("invalid URL `{}` of `{}`", url, name)))
+}
+
+fn apply_auth(req: &mut HttpRequest, auth: &Value) {
+    let kind = auth.get("type").and_then(Value::as_str).unwrap_or("noauth");
+    // Parameters are a list of `{key, value}` under the name of the type.
+    let param = |key: &str| {
+        auth.get(kind)
+            .and_then(Value::as_array)
+            .and_then(|params| {
+                params
+                    .iter()
+                    .find(|p| p.get("key").and_then(Value::as_str) == Some(key))
+            })
+            .map(|p| value_str(p.get("value")))
+            .unwrap_or_default()
+    };
+
+    match kind {
+        "noauth" => {}
+        "bearer" => {
+            let value = format!("Bearer {}", param("token"));
+            req.headers.push(header("Authorization", &value));
+        }
+        "basic" => basic_auth(req, &param("username"), &param("password")),
+        "apikey" => {
+            let (key, value) = (param("key"), param("value"));
+            if param("in") == "query" {
+                let param = format!("{}={}", query::encode(&key), query::encode(&value));
+                req.url.push_query(&param);
+            } else {
+                req.headers.push(header(&key, &value));
+            }
+        }
+        other => push_comment(req, &format!("# TODO: {} auth isn't supported", other)),
+    }
+}
+
+fn apply_body(req: &mut HttpRequest, body: &Value) {
+    let mode = body.get("mode").and_then(Value::as_str).unwrap_or_default();
+    let content_type = match mode {
+        "raw" => {
+            req.body = Body::Text(value_str(body.get("raw")));
+            let language = body
+                .pointer("/options/raw/language")
+                .and_then(Value::as_str);
+            match language {
+                Some("json") => Some("application/json".to_string()),
+                Some("xml") => Some("application/xml".to_string()),
+                Some("html") => Some("text/html".to_string()),
+                Some("javascript") => Some("application/javascript".to_string()),
+                _ => None,
+            }
+        }
+        "urlencoded" => {
+            let pairs: Vec<_> = enabled(body.get("urlencoded"))
+                .map(|p| {
+                    let key = query::encode(&value_str(p.get("key")));
+                    format!("{}={}", key, query::encode(&value_str(p.get("value"))))
+                })
+                .collect();
+            req.body = Body::Text(pairs.join("&"));
+            Some("application/x-www-form-urlencoded".to_string())
+        }
+        "formdata" => {
+            let parts = enabled(body.get("formdata")).map(form_part).collect();
+            req.body = Body::Multipart(Multipart {
+                boundary: BOUNDARY.to_string(),
+                parts,
+            });
+            Some(format!("multipart/form-data; boundary={}", BOUNDARY))
+        }
+        "file" => {
+            let src = body.pointer("/file/src").and_then(Value::as_str);
+            if let Some(src) = src {
+                req.body = Body::File {
+                    path: PathBuf::from(src),
+                    substitute: false,
+                };
+            }
+            None
+        }
+        "graphql" => {
+            let graphql = body.get("graphql").unwrap_or(&Value::Null);
+            let variables = graphql
+                .get("variables")
+                .and_then(Value::as_str)
+                .and_then(|vars| serde_json::from_str(vars).ok())
+                .unwrap_or_else(|| json!({}));
+            let payload = json!({
+                "query": value_str(graphql.get("query")),
+                "variables": variables,
+            });
+            req.body = Body::Text(serde_json::to_string_pretty(&payload).unwrap_or_default());
+            Some("application/json".to_string())
+        }
+        _ => None,
+    };
+
+    if let Some(content_type) = content_type {
+        let multipart = matches!(req.body, Body::Multipart(_));
+        if multipart {
+            req.headers
+                .retain(|h| !h.name.eq_ignore_ascii_case("content-type"));
+        }
+        if req.header("content-type").is_none() {
+            req.headers.push(header("Content-Type", &content_type));
+        }
+    }
+}
+
+fn form_part(field: &Value) -> Part {
+    let key = value_str(field.get("key"));
+    let mut disposition = format!("form-data; name=\"{}\"", key);
+    let body = if field.get("type").and_then(Value::as_str) == Some("file") {
+        // `src` is a list when several files were picked, only the first one is kept.
+        let src = match field.get("src") {
+            Some(Value::Array(files)) => value_str(files.first()),
+            src => value_str(src),
+        };
+        let path = PathBuf::from(src);
+        if let Some(name) = path.file_name() {
+            disposition.push_str(&format!("; filename=\"{}\"", name.to_string_lossy()));
+        }
+        Body::File {
+            path,
+            substitute: false,
+        }
+    } else {
+        Body::Text(value_str(field.get("value")))
+    };
+
+    let mut headers = vec![header("Content-Disposition", &disposition)];
+    if let Some(mime) = field.get("contentType").and_then(Value::as_str) {
+        headers.push(header("Content-Type", mime));
+    }
+    Part { headers, body }
+}
+
+fn value_str(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::{parse, write_collection, Error, Method};
+
+    const COLLECTION: &str = r#"{
+        "info": {"name": "Users", "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"},
+        "auth": {"type": "bearer", "bearer": [{"key": "token", "value": "{{token}}", "type": "string"}]},
+        "variable": [{"key": "baseUrl", "value": "https://example.com"}, {"key": "old", "value": "x", "disabled": true}],
+        "item": [
+            {"name": "Health", "request": "{{baseUrl}}/health"},
+            {"name": "Users", "item": [
+                {"name": "Create user", "request": {
+                    "method": "POST",
+                    "header": [{"key": "X-Trace", "value": "1"}, {"key": "X-Off", "value": "1", "disabled": true}],
+                    "body": {"mode": "raw", "raw": "{\"name\": \"sample\"}", "options": {"raw": {"language": "json"}}},
+                    "url": {"raw": "{{baseUrl}}/users", "host": ["{{baseUrl}}"], "path": ["users"]}
+                }},
+                {"name": "Avatars", "item": [
+                    {"name": "Upload", "request": {
+                        "method": "PUT",
+                        "auth": {"type": "noauth"},
+                        "body": {"mode": "formdata", "formdata": [
+                            {"key": "title", "value": "me", "type": "text"},
+                            {"key": "file", "src": "./me.png", "type": "file"}
+                        ]},
+                        "url": {"protocol": "https", "host": ["example", "com"], "path": ["avatars"], "query": [{"key": "size", "value": "large"}]}
+                    }}
+                ]},
+                {"name": "Log in", "request": {
+                    "method": "POST",
+                    "auth": {"type": "basic", "basic": [{"key": "username", "value": "user"}, {"key": "password", "value": "pass"}]},
+                    "body": {"mode": "urlencoded", "urlencoded": [{"key": "remember", "value": "yes please"}]},
+                    "url": "{{baseUrl}}/login"
+                }}
+            ]}
+        ]
+    }"#;
+
+    #[test]
+    fn import_collection() -> Result<()> {
+        let collection = import_postman(COLLECTION)?;
+        let mut out = Vec::new();
+        write_collection(&mut out, &collection)?;
+        let text = String::from_utf8(out).unwrap();
+
+        assert_eq!(
+            text,
+            r#"@baseUrl = https://example.com
+
+###
+# Health
+GET {{baseUrl}}/health HTTP/1.1
+Authorization: Bearer {{token}}
+
+### Users
+# Create user
+POST {{baseUrl}}/users HTTP/1.1
+X-Trace: 1
+Authorization: Bearer {{token}}
+Content-Type: application/json
+
+{"name": "sample"}
+
+###
+# Log in
+POST {{baseUrl}}/login HTTP/1.1
+Authorization: Basic dXNlcjpwYXNz
+Content-Type: application/x-www-form-urlencoded
+
+remember=yes%20please
+
+### Users / Avatars
+# Upload
+PUT https://example.com/avatars?size=large HTTP/1.1
+Content-Type: multipart/form-data; boundary=boundary
+
+--boundary
+Content-Disposition: form-data; name="title"
+
+me
+--boundary
+Content-Disposition: form-data; name="file"; filename="me.png"
+
+< ./me.png
+--boundary--
+
+"#
+        );
+
+        let document = parse(io::Cursor::new(text))?;
+        assert_eq!(document.requests.len(), 4);
+        assert_eq!(document.requests[3].method, Method::Put);
+        assert_eq!(document.variables[0].name, "baseUrl");
+
+        Ok(())
+    }
+
+    #[test]
+    fn import_invalid() {
+        assert!(matches!(
+            import_postman(r#"{"info": {}}"#),
+            Err(Error::InvalidImport { .. })
+        ));
+        assert!(matches!(
+            import_postman("not json"),
+            Err(Error::InvalidImport { .. })
+        ));
+    }
+}
@@ -1,3 +1,17 @@
+mod environment;
+mod export;
+mod import;
+mod lint;
 mod parser;
+mod response;
+mod variables;
+mod writer;
 
+pub use environment::*;
+pub use export::*;
+pub use import::*;
+pub use lint::*;
 pub use parser::*;
+pub use response::*;
+pub use variables::*;
+pub use writer::*;
@@ -0,0 +1,440 @@
+//! Checks for mistakes that would otherwise only show up when a request is sent.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use crate::parser::is_json;
+use crate::variables::{self, Resolver};
+use crate::{
+    parse_recovering, parse_syntax, Body, Document, Error, HttpRequest, Line, LineKind, Method,
+    RequestSyntax, Result, Severity, Span,
+};
+
+/// Headers that may only appear once in a request.
+const SINGLE_HEADERS: [&str; 4] = ["Content-Type", "Content-Length", "Host", "Authorization"];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Rule {
+    /// A header that may only appear once is set again.
+    DuplicateHeader,
+    /// The body is declared as JSON but doesn't parse.
+    InvalidJson,
+    /// A body is sent without a `Content-Type` header.
+    MissingContentType,
+    /// A `{{name}}` placeholder that no variable, environment or request defines.
+    UndefinedVariable,
+    /// The method isn't one of the RFC 9110 or PATCH methods.
+    NonStandardMethod,
+    /// A `# @directive` the parser didn't understand.
+    InvalidDirective,
+    /// A request the parser had to skip.
+    InvalidSyntax,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 7] = [
+        Rule::DuplicateHeader,
+        Rule::InvalidJson,
+        Rule::MissingContentType,
+        Rule::UndefinedVariable,
+        Rule::NonStandardMethod,
+        Rule::InvalidDirective,
+        Rule::InvalidSyntax,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rule::DuplicateHeader => "duplicate-header",
+            Rule::InvalidJson => "invalid-json",
+            Rule::MissingContentType => "missing-content-type",
+            Rule::UndefinedVariable => "undefined-variable",
+            Rule::NonStandardMethod => "non-standard-method",
+            Rule::InvalidDirective => "invalid-directive",
+            Rule::InvalidSyntax => "invalid-syntax",
+        }
+    }
+
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            Rule::DuplicateHeader
+            | Rule::InvalidJson
+            | Rule::UndefinedVariable
+            | Rule::InvalidSyntax => Severity::Error,
+            Rule::MissingContentType | Rule::NonStandardMethod | Rule::InvalidDirective => {
+                Severity::Warning
+            }
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Rule> {
+        Rule::ALL
+            .into_iter()
+            .find(|rule| rule.as_str() == text)
+            .ok_or_else(|| Error::UnknownRule(text.to_string()))
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Severities of the rules, rules that aren't configured keep their default.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct LintConfig {
+    severities: HashMap<Rule, Option<Severity>>,
+}
+
+impl LintConfig {
+    /// Changes the severity of `rule`, `None` turns it off.
+    pub fn set(&mut self, rule: Rule, severity: Option<Severity>) {
+        self.severities.insert(rule, severity);
+    }
+
+    pub fn severity(&self, rule: Rule) -> Option<Severity> {
+        match self.severities.get(&rule) {
+            Some(severity) => *severity,
+            None => Some(rule.default_severity()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Lint {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub span: Span,
+    pub message: String,
+}
+
+/// Checks the requests of a .http file, placeholders are also looked up in `resolver`.
+pub fn lint<R: Resolver + ?Sized>(
+    source: &str,
+    resolver: &R,
+    config: &LintConfig,
+) -> Result<Vec<Lint>> {
+    let document = parse_recovering(io::Cursor::new(source))?;
+    let tree = parse_syntax(source);
+    let variables = (
+        document.variables.as_slice(),
+        (ResponseRefs(&document), resolver),
+    );
+
+    let mut linter = Linter {
+        config,
+        lints: Vec::new(),
+    };
+    for diagnostic in &document.diagnostics {
+        // The parser only warns about directives, its errors are the requests it skipped.
+        let rule = match diagnostic.severity {
+            Severity::Warning => Rule::InvalidDirective,
+            Severity::Error => Rule::InvalidSyntax,
+        };
+        linter.push(rule, diagnostic.span, diagnostic.message.clone());
+    }
+    // Skipped requests are still in the tree, so requests are found by their request line.
+    let syntax: HashMap<_, _> = tree
+        .blocks
+        .iter()
+        .filter_map(|block| block.request.as_ref())
+        .map(|syntax| (syntax.request_line.span.line, syntax))
+        .collect();
+    for req in &document.requests {
+        if let Some(syntax) = syntax.get(&req.span.line) {
+            linter.request(req, syntax, &variables);
+        }
+    }
+
+    linter.lints.sort_by_key(|lint| lint.span.start);
+    Ok(linter.lints)
+}
+
+/// Stands in for responses of named requests, which only exist at send time.
+struct ResponseRefs<'a>(&'a Document);
+
+impl Resolver for ResponseRefs<'_> {
+    fn resolve(&self, name: &str) -> Option<String> {
+        let (request, rest) = name.split_once('.')?;
+        let named = self
+            .0
+            .requests
+            .iter()
+            .any(|req| req.name.as_deref() == Some(request));
+        (named && rest.starts_with("response.")).then(String::new)
+    }
+}
+
+struct Linter<'a> {
+    config: &'a LintConfig,
+    lints: Vec<Lint>,
+}
+
+impl Linter<'_> {
+    fn push(&mut self, rule: Rule, span: Span, message: String) {
+        if let Some(severity) = self.config.severity(rule) {
+            self.lints.push(Lint {
+                rule,
+                severity,
+                span,
+                message,
+            });
+        }
+    }
+
+    fn request<R: Resolver + ?Sized>(
+        &mut self,
+        req: &HttpRequest,
+        syntax: &RequestSyntax,
+        variables: &R,
+    ) {
+        let request_line = &syntax.request_line;
+
+        if let Method::Extension(method) = &req.method {
+            if !method.contains("{{") {
+                self.push(
+                    Rule::NonStandardMethod,
+                    span_at(
+                        request_line,
+                        request_line.text.find(method.as_str()).unwrap_or(0),
+                        method.len(),
+                    ),
+                    format!("`{}` isn't a standard method", method),
+                );
+            }
+        }
+
+        for (i, h) in req.headers.iter().enumerate() {
+            let single = SINGLE_HEADERS
+                .iter()
+                .find(|name| name.eq_ignore_ascii_case(&h.name));
+            let first = req.headers[..i]
+                .iter()
+                .find(|other| other.name.eq_ignore_ascii_case(&h.name));
+            if let (Some(name), Some(first)) = (single, first) {
+                self.push(
+                    Rule::DuplicateHeader,
+                    h.span,
+                    format!("{} is already set at line {}", name, first.span.line),
+                );
+            }
+        }
+
+        let has_body = match &req.body {
+            Body::Text(text) => !text.is_empty(),
+            Body::File { .. } | Body::Multipart(_) => true,
+        };
+        if has_body && req.header("content-type").is_none() {
+            self.push(
+                Rule::MissingContentType,
+                request_line.span,
+                String::from("the body is sent without a Content-Type header"),
+            );
+        }
+
+        if let (Body::Text(text), Some(content_type)) = (&req.body, req.header("content-type")) {
+            if is_json(content_type) {
+                self.json(text, &syntax.body);
+            }
+        }
+
+        let lines = syntax.lines().filter(|line| {
+            matches!(
+                line.kind,
+                LineKind::RequestLine
+                    | LineKind::Query
+                    | LineKind::Header
+                    | LineKind::Folded
+                    | LineKind::Body
+            )
+        });
+        for line in lines {
+            self.placeholders(line, variables);
+        }
+    }
+
+    /// Placeholders become numbers of the same length, valid both in strings and as values.
+    fn json(&mut self, text: &str, lines: &[Line]) {
+        let mut json = text.to_string();
+        for (start, end) in placeholders(text) {
+            json.replace_range(start..end, &format!("{:>1$}", 0, end - start));
+        }
+        let err = match serde_json::from_str::<serde_json::Value>(&json) {
+            Ok(_) => return,
+            Err(err) => err,
+        };
+        let line = match lines.get(err.line().saturating_sub(1)).or(lines.last()) {
+            Some(line) => line,
+            None => return,
+        };
+
+        let content = line.content();
+        let mut offset = err.column().saturating_sub(1).min(content.len());
+        while !content.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let len = content[offset..].chars().next().map_or(0, char::len_utf8);
+        let message = err.to_string();
+        let message = match message.rsplit_once(" at line ") {
+            Some((message, _)) => message,
+            None => &message,
+        };
+        self.push(
+            Rule::InvalidJson,
+            span_at(line, offset, len),
+            format!("body isn't valid JSON: {}", message),
+        );
+    }
+
+    fn placeholders<R: Resolver + ?Sized>(&mut self, line: &Line, variables: &R) {
+        for (start, end) in placeholders(&line.text) {
+            let placeholder = &line.text[start..end];
+            let message = match variables::substitute(placeholder, line.span.line, variables) {
+                Ok(_) => continue,
+                Err(Error::UndefinedVariable { name, .. }) => {
+                    format!("`{}` is not defined", name)
+                }
+                Err(Error::VariableRecursion { name, .. }) => {
+                    format!("`{}` references itself", name)
+                }
+                Err(err) => err.to_string(),
+            };
+            self.push(
+                Rule::UndefinedVariable,
+                span_at(line, start, end - start),
+                message,
+            );
+        }
+    }
+}
+
+/// Byte ranges of the `{{name}}` placeholders in `text`.
+fn placeholders(text: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut from = 0;
+    while let Some(start) = text[from..].find("{{").map(|i| from + i) {
+        let end = match text[start..].find("}}") {
+            Some(end) => start + end + 2,
+            None => break,
+        };
+        ranges.push((start, end));
+        from = end;
+    }
+    ranges
+}
+
+/// Span of `len` bytes at byte `offset` of the text of `line`.
+fn span_at(line: &Line, offset: usize, len: usize) -> Span {
+    let indent = line.text.len() - line.text.trim_start().len();
+    let start = line.span.start - indent + offset;
+    Span {
+        start,
+        end: start + len,
+        line: line.span.line,
+        column: line.text[..offset].chars().count() + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn rules(lints: &[Lint]) -> Vec<(Rule, usize, usize)> {
+        lints
+            .iter()
+            .map(|lint| (lint.rule, lint.span.line, lint.span.column))
+            .collect()
+    }
+
+    #[test]
+    fn lint_rules() -> Result<()> {
+        let source = "@host = {{scheme}}://example.com
+# @name login
+# @no-redirects
+POST {{host}}/login
+Content-Type: application/json
+content-type: text/plain
+
+{\"user\": \"{{user}}\", \"id\": {{id}},}
+###
+GET {{host}}/broken
+bad header
+###
+PURGE {{host}}/cache/{{login.response.body.$.id}}
+Authorization: Bearer {{other.response.body.token}}
+
+<./payload.bin
+";
+        let env = HashMap::from([
+            ("scheme".to_owned(), "https".to_owned()),
+            ("user".to_owned(), "sample".to_owned()),
+            ("id".to_owned(), "1".to_owned()),
+        ]);
+
+        let lints = lint(source, &env, &LintConfig::default())?;
+
+        assert_eq!(
+            rules(&lints),
+            [
+                (Rule::InvalidDirective, 3, 1),
+                (Rule::DuplicateHeader, 6, 1),
+                (Rule::InvalidJson, 8, 35),
+                (Rule::InvalidSyntax, 11, 11),
+                (Rule::NonStandardMethod, 13, 1),
+                (Rule::MissingContentType, 13, 1),
+                (Rule::UndefinedVariable, 14, 23),
+            ]
+        );
+        assert_eq!(lints[1].message, "Content-Type is already set at line 5");
+        assert_eq!(lints[1].severity, Severity::Error);
+        assert_eq!(lints[3].message, "invalid header value");
+        assert_eq!(
+            lints[6].message,
+            "`other.response.body.token` is not defined"
+        );
+        assert_eq!(
+            &source[lints[6].span.start..lints[6].span.end],
+            "{{other.response.body.token}}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn lint_config() -> Result<()> {
+        let source = "@a = {{b}}
+@b = {{a}}
+FOO https://example.com/{{a}}/{{missing}}
+";
+        let mut config = LintConfig::default();
+        config.set(Rule::NonStandardMethod, None);
+        config.set(Rule::UndefinedVariable, Some(Severity::Warning));
+
+        let lints = lint(source, &HashMap::new(), &config)?;
+
+        assert_eq!(
+            rules(&lints),
+            [
+                (Rule::UndefinedVariable, 3, 25),
+                (Rule::UndefinedVariable, 3, 31),
+            ]
+        );
+        assert!(lints.iter().all(|l| l.severity == Severity::Warning));
+        assert_eq!(lints[0].message, "`a` references itself");
+        assert_eq!(lints[1].message, "`missing` is not defined");
+        assert!(matches!(
+            "unknown".parse::<Rule>(),
+            Err(Error::UnknownRule(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,255 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::{Header, Result, Span};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Body {
+    Text(String),
+    /// `< ./path` reads the body from a file, `<@ ./path` also substitutes variables in it.
+    File {
+        path: PathBuf,
+        substitute: bool,
+    },
+    /// `multipart/form-data` split into parts using the boundary from `Content-Type`.
+    Multipart(Multipart),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Multipart {
+    pub boundary: String,
+    pub parts: Vec<Part>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Part {
+    pub headers: Vec<Header>,
+    /// Either inline text or a file reference, never nested multipart.
+    pub body: Body,
+}
+
+impl Default for Body {
+    fn default() -> Self {
+        Body::Text(String::new())
+    }
+}
+
+impl From<&str> for Body {
+    fn from(text: &str) -> Self {
+        Body::Text(text.to_string())
+    }
+}
+
+impl Body {
+    /// Recognizes a file reference or multipart parts, otherwise keeps `text` as is.
+    /// `span` is where `text` starts in the source.
+    pub(crate) fn parse(text: String, content_type: Option<&str>, span: Span) -> Result<Body> {
+        if let Some(boundary) = content_type.and_then(multipart_boundary) {
+            if let Some(multipart) = Multipart::parse(&text, boundary, span)? {
+                return Ok(Body::Multipart(multipart));
+            }
+        }
+
+        Ok(Body::parse_part(text))
+    }
+
+    fn parse_part(text: String) -> Body {
+        if !text.contains('\n') {
+            if let Some(path) = file_reference(&text, "<@") {
+                return Body::File {
+                    path: PathBuf::from(path),
+                    substitute: true,
+                };
+            }
+            if let Some(path) = file_reference(&text, "<") {
+                return Body::File {
+                    path: PathBuf::from(path),
+                    substitute: false,
+                };
+            }
+        }
+
+        Body::Text(text)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Body::Text(text) => text.is_empty(),
+            Body::File { .. } => false,
+            Body::Multipart(multipart) => multipart.parts.is_empty(),
+        }
+    }
+
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Body::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Makes a relative file path relative to `dir`.
+    pub(crate) fn rebase(&mut self, dir: &Path) {
+        match self {
+            Body::File { path, .. } if path.is_relative() => *path = dir.join(&path),
+            Body::Multipart(multipart) => {
+                for part in multipart.parts.iter_mut() {
+                    part.body.rebase(dir);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Body::Text(text) => f.write_str(text),
+            Body::File { path, substitute } => {
+                let marker = if *substitute { "<@" } else { "<" };
+                write!(f, "{} {}", marker, path.display())
+            }
+            Body::Multipart(multipart) => {
+                for part in &multipart.parts {
+                    writeln!(f, "--{}", multipart.boundary)?;
+                    for h in &part.headers {
+                        writeln!(f, "{}: {}", h.name, h.value)?;
+                    }
+                    writeln!(f)?;
+                    writeln!(f, "{}", part.body)?;
+                }
+                write!(f, "--{}--", multipart.boundary)
+            }
+        }
+    }
+}
+
+impl Part {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case(name))
+            .map(|h| h.value.as_str())
+    }
+
+    /// `name` parameter of `Content-Disposition`.
+    pub fn name(&self) -> Option<&str> {
+        disposition_param(self.header("content-disposition")?, "name")
+    }
+
+    /// `filename` parameter of `Content-Disposition`.
+    pub fn file_name(&self) -> Option<&str> {
+        disposition_param(self.header("content-disposition")?, "filename")
+    }
+}
+
+impl Multipart {
+    /// Returns `None` when `text` doesn't start with the boundary delimiter.
+    fn parse(text: &str, boundary: &str, span: Span) -> Result<Option<Multipart>> {
+        let delimiter = format!("--{}", boundary);
+        let close = format!("--{}--", boundary);
+
+        let mut lines = Vec::new();
+        let mut offset = span.start;
+        for (i, raw) in text.split_inclusive('\n').enumerate() {
+            lines.push((raw, Span::of_line(raw, offset, span.line + i)));
+            offset += raw.len();
+        }
+        let mut lines = lines.into_iter();
+
+        match lines.next() {
+            Some((first, _)) if first.trim_end() == delimiter => {}
+            _ => return Ok(None),
+        }
+
+        let mut parts = Vec::new();
+        loop {
+            let mut headers = Vec::new();
+            for (raw, span) in lines.by_ref() {
+                let line = raw.trim();
+                if line.is_empty() {
+                    break;
+                }
+                headers.push(Header::parse(line, span)?);
+            }
+
+            let mut content = String::new();
+            let mut last = true;
+            for (raw, _) in lines.by_ref() {
+                if raw.trim_end() == delimiter {
+                    last = false;
+                    break;
+                }
+                if raw.trim_end() == close {
+                    break;
+                }
+                content.push_str(raw);
+            }
+            // The line break before a delimiter belongs to the delimiter.
+            if content.ends_with('\n') {
+                content.pop();
+                if content.ends_with('\r') {
+                    content.pop();
+                }
+            }
+
+            parts.push(Part {
+                headers,
+                body: Body::parse_part(content),
+            });
+            if last {
+                break;
+            }
+        }
+
+        Ok(Some(Multipart {
+            boundary: boundary.to_string(),
+            parts,
+        }))
+    }
+}
+
+fn file_reference<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix(marker)?;
+    let path = rest.trim_start();
+    if path.len() == rest.len() || path.is_empty() {
+        return None;
+    }
+    Some(path.trim_end())
+}
+
+/// `application/json` and `+json` types such as `application/problem+json`.
+pub(crate) fn is_json(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    mime.eq_ignore_ascii_case("application/json") || mime.to_ascii_lowercase().ends_with("+json")
+}
+
+fn multipart_boundary(content_type: &str) -> Option<&str> {
+    let (mime, params) = content_type.split_once(';')?;
+    if !mime.trim().eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    param(params, "boundary")
+}
+
+fn disposition_param<'a>(disposition: &'a str, name: &str) -> Option<&'a str> {
+    let (_, params) = disposition.split_once(';')?;
+    param(params, name)
+}
+
+/// Finds `name=value` or `name="value"` among `;` separated parameters.
+fn param<'a>(params: &'a str, name: &str) -> Option<&'a str> {
+    params.split(';').find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        if !key.trim().eq_ignore_ascii_case(name) {
+            return None;
+        }
+        let value = value.trim();
+        Some(
+            value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value),
+        )
+    })
+}
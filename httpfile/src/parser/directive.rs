@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Changes how a request is sent, written as a `# @directive [value]` comment.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash)]
+pub enum Directive {
+    /// Returns redirect responses instead of following them.
+    NoRedirect,
+    /// Neither sends nor stores cookies.
+    NoCookieJar,
+    /// Time allowed for the whole exchange, e.g. `@timeout 30` or `@timeout 500 ms`.
+    Timeout,
+    /// Time allowed to connect to the server.
+    ConnectionTimeout,
+}
+
+/// Directives of a request with their values, empty for flags.
+pub type Settings = BTreeMap<Directive, String>;
+
+impl Directive {
+    pub const ALL: [Directive; 4] = [
+        Directive::NoRedirect,
+        Directive::NoCookieJar,
+        Directive::Timeout,
+        Directive::ConnectionTimeout,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Directive::NoRedirect => "no-redirect",
+            Directive::NoCookieJar => "no-cookie-jar",
+            Directive::Timeout => "timeout",
+            Directive::ConnectionTimeout => "connection-timeout",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Directive> {
+        Directive::ALL.into_iter().find(|d| d.as_str() == name)
+    }
+
+    /// Checks the value of the directive, the message explains what's wrong with it.
+    pub(crate) fn validate(&self, value: &str) -> Result<(), String> {
+        match self {
+            Directive::NoRedirect | Directive::NoCookieJar if !value.is_empty() => {
+                Err(format!("@{} doesn't take a value", self))
+            }
+            Directive::Timeout | Directive::ConnectionTimeout if parse_duration(value).is_none() => {
+                Err(format!(
+                    "invalid duration `{}` for @{}, expected a number with an optional ms, s or m unit",
+                    value, self
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Directive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Splits `@name value` and `@name=value` comment text, `None` when it isn't a directive.
+pub(crate) fn parse_directive(comment: &str) -> Option<(&str, &str)> {
+    let rest = comment.trim_start().strip_prefix('@')?;
+    let end = rest
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(rest.len());
+    if end == 0 {
+        return None;
+    }
+    let value = rest[end..].trim_start_matches(|c: char| c.is_whitespace() || c == '=');
+    Some((&rest[..end], value.trim_end()))
+}
+
+/// The known directive `name` is likely a typo of, plain `# @word` comments don't get one.
+pub(crate) fn suggest(name: &str) -> Option<&'static str> {
+    Directive::ALL
+        .iter()
+        .map(Directive::as_str)
+        .chain(["name"])
+        .find(|known| distance(name, known) <= 2)
+}
+
+/// Levenshtein distance between `a` and `b`, in characters.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Seconds by default, `ms`, `s` and `m` units may follow the number with or without a space.
+pub(crate) fn parse_duration(text: &str) -> Option<Duration> {
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let amount: u64 = text[..split].parse().ok()?;
+    match text[split..].trim_start() {
+        "ms" => Some(Duration::from_millis(amount)),
+        "" | "s" => Some(Duration::from_secs(amount)),
+        "m" => Some(Duration::from_secs(amount.checked_mul(60)?)),
+        _ => None,
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::{invalid_token_char, Error};
+
+/// Request method, the standard ones are recognized regardless of case.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Method {
+    Get,
+    Head,
+    Post,
+    Put,
+    Delete,
+    Connect,
+    Options,
+    Trace,
+    Patch,
+    /// Any other token, kept as written. May contain `{{name}}` placeholders.
+    Extension(String),
+}
+
+const STANDARD: [(Method, &str); 9] = [
+    (Method::Get, "GET"),
+    (Method::Head, "HEAD"),
+    (Method::Post, "POST"),
+    (Method::Put, "PUT"),
+    (Method::Delete, "DELETE"),
+    (Method::Connect, "CONNECT"),
+    (Method::Options, "OPTIONS"),
+    (Method::Trace, "TRACE"),
+    (Method::Patch, "PATCH"),
+];
+
+impl Method {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Method::Extension(text) => text,
+            standard => STANDARD
+                .iter()
+                .find(|(m, _)| m == standard)
+                .map_or("", |(_, text)| text),
+        }
+    }
+
+    /// Byte offset of the first character that can't be part of a method.
+    pub(crate) fn invalid_char(text: &str) -> Option<usize> {
+        if text.is_empty() {
+            return Some(0);
+        }
+        invalid_token_char(text)
+    }
+}
+
+impl FromStr for Method {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Method, Error> {
+        if Method::invalid_char(text).is_some() {
+            return Err(Error::InvalidMethod);
+        }
+        let method = STANDARD
+            .iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(text))
+            .map_or_else(|| Method::Extension(text.to_string()), |(m, _)| m.clone());
+
+        Ok(method)
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Method and version as written, when that differs from the canonical form, e.g. `get`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Spelling {
+    pub method: Option<String>,
+    pub version: Option<String>,
+}
+
+impl Spelling {
+    pub(crate) fn new(method: &str, version: Option<&str>) -> Spelling {
+        let differs = |text: &str, canonical: &str| (text != canonical).then(|| text.to_string());
+        Spelling {
+            method: method
+                .parse::<Method>()
+                .ok()
+                .and_then(|m| differs(method, m.as_str())),
+            version: version.and_then(|v| differs(v, v.parse::<HttpVersion>().ok()?.as_str())),
+        }
+    }
+}
+
+/// Protocol version from the request line, HTTP/1.1 when omitted.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum HttpVersion {
+    Http09,
+    Http10,
+    #[default]
+    Http11,
+    Http2,
+    Http3,
+}
+
+impl HttpVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpVersion::Http09 => "HTTP/0.9",
+            HttpVersion::Http10 => "HTTP/1.0",
+            HttpVersion::Http11 => "HTTP/1.1",
+            HttpVersion::Http2 => "HTTP/2",
+            HttpVersion::Http3 => "HTTP/3",
+        }
+    }
+
+    /// `None` for versions reqwest can't send.
+    #[cfg(feature = "reqwest")]
+    pub(crate) fn to_reqwest(self) -> Option<reqwest::Version> {
+        match self {
+            HttpVersion::Http10 => Some(reqwest::Version::HTTP_10),
+            HttpVersion::Http11 => Some(reqwest::Version::HTTP_11),
+            HttpVersion::Http2 => Some(reqwest::Version::HTTP_2),
+            HttpVersion::Http09 | HttpVersion::Http3 => None,
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    pub fn from_reqwest(version: reqwest::Version) -> Option<HttpVersion> {
+        let version = match version {
+            reqwest::Version::HTTP_09 => HttpVersion::Http09,
+            reqwest::Version::HTTP_10 => HttpVersion::Http10,
+            reqwest::Version::HTTP_11 => HttpVersion::Http11,
+            reqwest::Version::HTTP_2 => HttpVersion::Http2,
+            reqwest::Version::HTTP_3 => HttpVersion::Http3,
+            _ => return None,
+        };
+
+        Some(version)
+    }
+}
+
+/// Accepts both `HTTP/2` and `HTTP/2.0` forms.
+impl FromStr for HttpVersion {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<HttpVersion, Error> {
+        let number = text.strip_prefix("HTTP/").ok_or(Error::InvalidVersion)?;
+        let version = match number {
+            "0.9" => HttpVersion::Http09,
+            "1.0" => HttpVersion::Http10,
+            "1.1" => HttpVersion::Http11,
+            "2" | "2.0" => HttpVersion::Http2,
+            "3" | "3.0" => HttpVersion::Http3,
+            _ => return Err(Error::InvalidVersion),
+        };
+
+        Ok(version)
+    }
+}
+
+impl fmt::Display for HttpVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -1,8 +1,30 @@
+mod body;
+mod diagnostic;
+mod directive;
+mod method;
+pub(crate) mod query;
+mod span;
+mod syntax;
+mod url;
+
 use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use thiserror::Error;
 
-type Result<T> = std::result::Result<T, Error>;
+pub(crate) use body::is_json;
+pub use body::{Body, Multipart, Part};
+pub use diagnostic::{Diagnostic, Severity};
+pub use directive::{Directive, Settings};
+pub use method::{HttpVersion, Method, Spelling};
+pub use span::Span;
+pub use syntax::{parse_syntax, Block, Line, LineKind, RequestSyntax, SyntaxTree};
+pub use url::Url;
+
+use crate::variables::{self, Resolver, Variable};
+
+pub(crate) type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -24,17 +46,53 @@ pub enum Error {
     NoMethod,
     #[error("couldn't parse http url")]
     NoUrl,
+    #[error("invalid method")]
+    InvalidMethod,
+    #[error("invalid URL")]
+    InvalidURL,
+    #[error("unsupported HTTP version")]
+    InvalidVersion,
     #[error("invalid header name")]
     InvalidHeaderName,
     #[error("invalid header value")]
     InvalidHeaderValue,
+    #[error("{source} at line {line}, column {column}")]
+    Syntax {
+        line: usize,
+        column: usize,
+        source: Box<Error>,
+    },
+    #[error("undefined variable `{name}` at line {line}")]
+    UndefinedVariable { name: String, line: usize },
+    #[error("variable `{name}` at line {line} references itself")]
+    VariableRecursion { name: String, line: usize },
+    #[error("couldn't read {}: {source}", path.display())]
+    ReadFile { path: PathBuf, source: io::Error },
+    #[error("invalid curl command: {0}")]
+    InvalidCurl(String),
+    #[error("couldn't import {format}: {message}")]
+    InvalidImport {
+        format: &'static str,
+        message: String,
+    },
+    #[error("unknown language `{0}`, expected rust, python, javascript or go")]
+    UnknownLanguage(String),
+    #[error("unknown header case `{0}`, expected preserve, lower or title")]
+    UnknownHeaderCase(String),
+    #[error("unknown lint rule `{0}`")]
+    UnknownRule(String),
+    #[error("invalid environment file {}: {source}", path.display())]
+    InvalidEnvFile {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
 
     #[cfg(feature = "reqwest")]
-    #[error("invalid method")]
-    InvalidMethod,
+    #[error("{0} is not supported")]
+    UnsupportedVersion(HttpVersion),
     #[cfg(feature = "reqwest")]
-    #[error("invalid URL")]
-    InvalidURL,
+    #[error("multipart part without a name")]
+    InvalidMultipart,
     #[cfg(feature = "reqwest")]
     #[error("{0}")]
     RequestError(#[from] reqwest::Error),
@@ -43,95 +101,395 @@ pub enum Error {
     Other(#[from] Box<dyn std::error::Error + Send + Sync>),
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Document {
+    pub variables: Vec<Variable>,
+    pub requests: Vec<HttpRequest>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Document {
+    /// Resolves relative paths of body files against `dir`, the directory of the .http file.
+    pub fn rebase(&mut self, dir: &Path) {
+        for req in self.requests.iter_mut() {
+            req.rebase(dir);
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct HttpRequest {
+    /// `#` and `//` comment lines, including the markers, separated by `\n`.
     pub comment: String,
-    pub method: String,
-    pub url: String,
-    pub version: String,
+    /// Set by `# @name`, used to reference the response from other requests.
+    pub name: Option<String>,
+    /// Set by `# @no-redirect`, `# @timeout 5` and other directives.
+    pub settings: Settings,
+    pub method: Method,
+    pub url: Url,
+    pub version: HttpVersion,
+    /// Source text of the method and version, see [`HttpRequest::method_text`].
+    pub spelling: Spelling,
     pub headers: Vec<Header>,
-    pub body: String,
+    pub body: Body,
+    /// From the request line to the end of the body.
+    pub span: Span,
+    pub body_span: Span,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Header {
     pub name: String,
     pub value: String,
+    pub span: Span,
 }
 
 impl Header {
-    fn parse(line: String) -> Result<Header> {
-        let mut parts = line.split(": ");
-        let name = parts.next().ok_or(Error::InvalidHeaderName)?.to_string();
-        let value = parts.next().ok_or(Error::InvalidHeaderValue)?.to_string();
-        Ok(Header { name, value })
+    /// Parses `name: value` as described in RFC 9110, section 5.
+    fn parse(line: &str, span: Span) -> Result<Header> {
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| span.error(line, line.len(), Error::InvalidHeaderValue))?;
+
+        if name.is_empty() {
+            return Err(span.error(line, 0, Error::InvalidHeaderName));
+        }
+        if let Some(i) = invalid_token_char(name) {
+            return Err(span.error(line, i, Error::InvalidHeaderName));
+        }
+        let value_start = name.len() + 1;
+        if let Some(i) = value.find(|c: char| c.is_control() && c != '\t') {
+            return Err(span.error(line, value_start + i, Error::InvalidHeaderValue));
+        }
+
+        Ok(Header {
+            name: name.to_string(),
+            value: value.trim_matches(is_ows).to_string(),
+            span,
+        })
+    }
+
+    /// Appends an obsolete line folding continuation to the value.
+    fn unfold(&mut self, line: &str, span: Span) -> Result<()> {
+        if let Some(i) = line.find(|c: char| c.is_control() && c != '\t') {
+            return Err(span.error(line, i, Error::InvalidHeaderValue));
+        }
+        if !self.value.is_empty() {
+            self.value.push(' ');
+        }
+        self.value.push_str(line.trim_matches(is_ows));
+        self.span.end = span.end;
+        Ok(())
+    }
+}
+
+/// Text after the `#` or `//` marker, `None` when `line` is not a comment.
+fn comment_text(line: &str) -> Option<&str> {
+    line.strip_prefix('#').or_else(|| line.strip_prefix("//"))
+}
+
+fn is_ows(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+/// Byte offset of the first character that isn't allowed in a token, placeholders are allowed.
+fn invalid_token_char(name: &str) -> Option<usize> {
+    let mut i = 0;
+    while i < name.len() {
+        let rest = &name[i..];
+        if rest.starts_with("{{") {
+            if let Some(end) = rest.find("}}") {
+                i += end + 2;
+                continue;
+            }
+        }
+
+        let c = rest.chars().next()?;
+        if !(c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)) {
+            return Some(i);
+        }
+        i += c.len_utf8();
     }
+
+    None
 }
 
 impl HttpRequest {
-    fn new() -> Self {
+    /// Records a comment `line`, `# @name` and known directives set the name and settings
+    /// instead. Invalid and misspelled directives are kept in the comment and warned about.
+    fn push_comment(&mut self, line: &str, comment: &str, span: Span) -> Option<Diagnostic> {
+        let mut warning = None;
+        if let Some((name, value)) = directive::parse_directive(comment) {
+            let res = match (name, Directive::from_name(name)) {
+                ("name", _) if !value.is_empty() => {
+                    self.name = Some(value.to_string());
+                    Ok(())
+                }
+                ("name", _) => Err(Some(String::from("@name needs a value"))),
+                (_, Some(directive)) => directive
+                    .validate(value)
+                    .map(|()| {
+                        self.settings.insert(directive, value.to_string());
+                    })
+                    .map_err(Some),
+                // Other names are plain comments such as `# @see`, unless they're a typo.
+                (name, None) => Err(directive::suggest(name)
+                    .map(|known| format!("unknown directive @{}, did you mean @{}?", name, known))),
+            };
+            match res {
+                Ok(()) => return None,
+                Err(message) => {
+                    warning = message.map(|message| Diagnostic {
+                        severity: Severity::Warning,
+                        span,
+                        message,
+                    })
+                }
+            }
+        }
+
+        if !self.comment.is_empty() {
+            self.comment.push('\n');
+        }
+        self.comment.push_str(line);
+        warning
+    }
+
+    /// Whether `# @directive` is set, for flags such as `@no-redirect`.
+    pub fn has(&self, directive: Directive) -> bool {
+        self.settings.contains_key(&directive)
+    }
+
+    /// From `# @timeout`.
+    pub fn timeout(&self) -> Option<Duration> {
+        directive::parse_duration(self.settings.get(&Directive::Timeout)?)
+    }
+
+    /// From `# @connection-timeout`.
+    pub fn connection_timeout(&self) -> Option<Duration> {
+        directive::parse_duration(self.settings.get(&Directive::ConnectionTimeout)?)
+    }
+
+    pub(crate) fn new() -> Self {
         HttpRequest {
             comment: String::new(),
-            method: String::new(),
-            url: String::new(),
-            version: String::from("HTTP/1.1"),
+            name: None,
+            settings: Settings::new(),
+            method: Method::Get,
+            url: Url::default(),
+            version: HttpVersion::default(),
+            spelling: Spelling::default(),
             headers: Vec::new(),
-            body: String::new(),
+            body: Body::default(),
+            span: Span::default(),
+            body_span: Span::default(),
         }
     }
 
     fn is_empty(&self) -> bool {
-        self.url.is_empty() || self.method.is_empty()
+        self.url.is_empty()
+    }
+
+    /// Drops whitespace between the body and the request separator.
+    fn finish_body(&mut self, mut body: String) -> Result<()> {
+        let len = body.trim_end().len();
+        body.truncate(len);
+        self.body_span.end = self.body_span.start + len;
+        if len > 0 {
+            self.span.end = self.body_span.end;
+        }
+        self.body = Body::parse(body, self.header("content-type"), self.body_span)?;
+        Ok(())
+    }
+
+    /// Resolves relative paths of body files against `dir`.
+    pub fn rebase(&mut self, dir: &Path) {
+        self.body.rebase(dir);
     }
 
-    fn parse_method_url_version(&mut self, line: &str) -> Result<()> {
-        // let line = line.trim();
+    /// Decoded query parameters of the url.
+    pub fn query(&self) -> Vec<(String, String)> {
+        self.url.query().map(query::pairs).unwrap_or_default()
+    }
+
+    /// The method as written in the source, e.g. `get` for [`Method::Get`].
+    pub fn method_text(&self) -> &str {
+        match &self.spelling.method {
+            Some(text) if text.parse().ok().as_ref() == Some(&self.method) => text,
+            _ => self.method.as_str(),
+        }
+    }
+
+    /// The version as written in the source, e.g. `HTTP/2.0`, `HTTP/1.1` when omitted.
+    pub fn version_text(&self) -> &str {
+        match &self.spelling.version {
+            Some(text) if text.parse().ok() == Some(self.version) => text,
+            _ => self.version.as_str(),
+        }
+    }
+
+    /// Value of the first header called `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case(name))
+            .map(|h| h.value.as_str())
+    }
+
+    fn parse_method_url_version(&mut self, line: &str, span: Span) -> Result<()> {
         let mut parts = line.split_whitespace();
-        let method = parts.next().ok_or(Error::NoMethod)?;
-        let url = parts.next().ok_or(Error::NoUrl)?;
+        let method = parts
+            .next()
+            .ok_or_else(|| span.error(line, 0, Error::NoMethod))?;
+        let url = parts
+            .next()
+            .ok_or_else(|| span.error(line, line.len(), Error::NoUrl))?;
         let version = parts.next();
 
-        self.method = method.to_string();
-        self.url = url.to_string();
+        let at = |part: &str, i: usize| part.as_ptr() as usize - line.as_ptr() as usize + i;
+        if let Some(i) = Method::invalid_char(method) {
+            return Err(span.error(line, at(method, i), Error::InvalidMethod));
+        }
+        if let Some(i) = Url::invalid_char(url) {
+            return Err(span.error(line, at(url, i), Error::InvalidURL));
+        }
+        self.method = method.parse()?;
+        self.url = url.parse()?;
         if let Some(version) = version {
-            self.version = version.to_string();
+            self.version = version
+                .parse()
+                .map_err(|err| span.error(line, at(version, 0), err))?;
         }
+        self.spelling = Spelling::new(method, version);
 
         Ok(())
     }
 
+    /// Returns a copy with `{{name}}` placeholders in the url, headers and body replaced.
+    pub fn resolve<R: Resolver + ?Sized>(&self, resolver: &R) -> Result<HttpRequest> {
+        let mut req = self.clone();
+        if let Method::Extension(method) = &self.method {
+            req.method = variables::substitute(method, self.span.line, resolver)?.parse()?;
+        }
+        req.url = variables::substitute(self.url.as_str(), self.span.line, resolver)?.parse()?;
+        for h in req.headers.iter_mut() {
+            h.name = variables::substitute(&h.name, h.span.line, resolver)?;
+            h.value = variables::substitute(&h.value, h.span.line, resolver)?;
+        }
+        req.body = resolve_body(&self.body, self.body_span.line, resolver)?;
+
+        Ok(req)
+    }
+
+    /// Builds the request with the version from the request line, `client` has to be able to
+    /// speak it: HTTP/2 over cleartext needs prior knowledge, over TLS it needs ALPN.
     #[cfg(feature = "reqwest")]
     pub fn to_reqwest(&self, client: &reqwest::Client) -> Result<reqwest::Request> {
-        let method = {
-            let method_upper = self.method.to_uppercase();
-            reqwest::Method::from_bytes(method_upper.as_bytes())
-                .map_err(|_| Error::InvalidMethod)?
-        };
-        let url = reqwest::Url::parse(&self.url).map_err(|_| Error::InvalidURL)?;
+        let method = reqwest::Method::from_bytes(self.method.as_str().as_bytes())
+            .map_err(|_| Error::InvalidMethod)?;
+        let url = reqwest::Url::parse(self.url.as_str()).map_err(|_| Error::InvalidURL)?;
 
         let mut req = client.request(method, url);
-        if !self.body.is_empty() {
-            req = req.body(self.body.clone());
+        match &self.body {
+            Body::Text(text) if !text.is_empty() => req = req.body(text.clone()),
+            Body::Text(_) => {}
+            Body::File { path, .. } => req = req.body(open_file(path)?),
+            Body::Multipart(multipart) => req = req.multipart(multipart_form(multipart)?),
         }
 
+        let multipart = matches!(self.body, Body::Multipart(_));
         for h in &self.headers {
+            // reqwest generates its own boundary for multipart bodies.
+            if multipart && h.name.eq_ignore_ascii_case("content-type") {
+                continue;
+            }
             req = req.header(h.name.to_string(), h.value.to_string());
         }
 
-        // use reqwest::Version;
-        // req = match self.version.as_str() {
-        //     "HTTP/0.9" => req.version(Version::HTTP_09),
-        //     "HTTP/1.0" => req.version(Version::HTTP_10),
-        //     "HTTP/1.1" => req.version(Version::HTTP_11),
-        //     "HTTP/2.0" => req.version(Version::HTTP_2),
-        //     "HTTP/3.0" => req.version(Version::HTTP_3),
-        //     _ => req.version(Version::default()),
-        // };
+        let version = self
+            .version
+            .to_reqwest()
+            .ok_or(Error::UnsupportedVersion(self.version))?;
+        req = req.version(version);
+        // Redirects, cookies and the connection timeout are set on the client.
+        if let Some(timeout) = self.timeout() {
+            req = req.timeout(timeout);
+        }
 
         Ok(req.build()?)
     }
 }
 
+fn resolve_body<R: Resolver + ?Sized>(body: &Body, line: usize, resolver: &R) -> Result<Body> {
+    let body = match body {
+        Body::Text(text) => Body::Text(variables::substitute(text, line, resolver)?),
+        Body::File {
+            path,
+            substitute: true,
+        } => {
+            let text = std::fs::read_to_string(path).map_err(|source| Error::ReadFile {
+                path: path.clone(),
+                source,
+            })?;
+            Body::Text(variables::substitute(&text, line, resolver)?)
+        }
+        Body::File { .. } => body.clone(),
+        Body::Multipart(multipart) => {
+            let mut multipart = multipart.clone();
+            for part in multipart.parts.iter_mut() {
+                let line = part.headers.last().map_or(line, |h| h.span.line + 2);
+                for h in part.headers.iter_mut() {
+                    h.value = variables::substitute(&h.value, h.span.line, resolver)?;
+                }
+                part.body = resolve_body(&part.body, line, resolver)?;
+            }
+            Body::Multipart(multipart)
+        }
+    };
+
+    Ok(body)
+}
+
+#[cfg(feature = "reqwest")]
+fn open_file(path: &Path) -> Result<tokio::fs::File> {
+    let file = std::fs::File::open(path).map_err(|source| Error::ReadFile {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(tokio::fs::File::from_std(file))
+}
+
+/// Only `Content-Disposition` and `Content-Type` of the parts are sent.
+#[cfg(feature = "reqwest")]
+fn multipart_form(multipart: &Multipart) -> Result<reqwest::multipart::Form> {
+    use reqwest::multipart::{Form, Part};
+
+    let mut form = Form::new();
+    for part in &multipart.parts {
+        let mut p = match &part.body {
+            Body::Text(text) => Part::text(text.clone()),
+            Body::File { path, .. } => {
+                let p = Part::stream(open_file(path)?);
+                match path.file_name() {
+                    Some(name) => p.file_name(name.to_string_lossy().into_owned()),
+                    None => p,
+                }
+            }
+            Body::Multipart(_) => return Err(Error::InvalidMultipart),
+        };
+        if let Some(name) = part.file_name() {
+            p = p.file_name(name.to_string());
+        }
+        if let Some(mime) = part.header("content-type") {
+            p = p.mime_str(mime)?;
+        }
+        let name = part.name().ok_or(Error::InvalidMultipart)?;
+        form = form.part(name.to_string(), p);
+    }
+
+    Ok(form)
+}
+
 #[derive(Debug, PartialEq, Eq)]
 enum State {
     Url,
@@ -139,12 +497,71 @@ enum State {
     Body,
 }
 
-pub fn parse<BR: io::BufRead>(r: BR) -> Result<Vec<HttpRequest>> {
-    Parser::new(r).collect()
+pub fn parse<BR: io::BufRead>(r: BR) -> Result<Document> {
+    let mut parser = Parser::new(r);
+    let requests = parser.by_ref().collect::<Result<_>>()?;
+
+    Ok(Document {
+        variables: parser.variables,
+        requests,
+        diagnostics: parser.diagnostics,
+    })
+}
+
+/// Like [`parse`], but skips requests with syntax errors and reports them as diagnostics.
+pub fn parse_recovering<BR: io::BufRead>(r: BR) -> Result<Document> {
+    let mut parser = Parser::new(r);
+    let mut requests = Vec::new();
+
+    while let Some(res) = parser.next() {
+        match res {
+            Ok(req) => requests.push(req),
+            Err(Error::Syntax {
+                line,
+                column,
+                source,
+            }) => {
+                // A broken body is only noticed once the request has ended, so there's
+                // nothing left to skip.
+                let (start, end) = match parser.broken_body.take() {
+                    Some(body) => (body.start, body.end),
+                    None => (parser.line_span.start, parser.skip_request()?),
+                };
+                parser.diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    span: Span {
+                        start,
+                        end,
+                        line,
+                        column,
+                    },
+                    message: source.to_string(),
+                });
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(Document {
+        variables: parser.variables,
+        requests,
+        diagnostics: parser.diagnostics,
+    })
 }
 
 struct Parser<BR: io::BufRead> {
     r: BR,
+    lineno: usize,
+    /// Bytes consumed so far.
+    offset: usize,
+    /// Span of the last line read.
+    line_span: Span,
+    variables: Vec<Variable>,
+    /// Warnings, and errors of skipped requests when recovering.
+    diagnostics: Vec<Diagnostic>,
+    /// From the line with the error to the end of a body that failed to parse after the
+    /// request had been read to its end.
+    broken_body: Option<Span>,
 }
 
 impl<BR: io::BufRead> Iterator for Parser<BR> {
@@ -162,13 +579,75 @@ impl<BR: io::BufRead> Iterator for Parser<BR> {
 
 impl<BR: io::BufRead> Parser<BR> {
     fn new(r: BR) -> Self {
-        Self { r }
+        Self {
+            r,
+            lineno: 0,
+            offset: 0,
+            line_span: Span::default(),
+            variables: Vec::new(),
+            diagnostics: Vec::new(),
+            broken_body: None,
+        }
+    }
+
+    /// Empty span at the start of the line that will be read next.
+    fn next_line(&self) -> Span {
+        Span {
+            start: self.offset,
+            end: self.offset,
+            line: self.lineno + 1,
+            column: 1,
+        }
+    }
+
+    /// Consumes lines up to the next request separator and returns where the skipped part ends.
+    fn skip_request(&mut self) -> Result<usize> {
+        let mut end = self.line_span.end;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let n = self.r.read_line(&mut line)?;
+            if n == 0 {
+                return Ok(end);
+            }
+            self.lineno += 1;
+            let span = Span::of_line(&line, self.offset, self.lineno);
+            self.offset += n;
+            self.line_span = span;
+
+            let line = line.trim();
+            if line.starts_with("###") {
+                return Ok(end);
+            }
+            if !line.is_empty() {
+                end = span.end;
+            }
+        }
+    }
+
+    /// Like [`HttpRequest::finish_body`], but remembers where a broken body starts to fail.
+    fn finish_body(&mut self, hc: &mut HttpRequest, body: String) -> Result<()> {
+        let text = body.clone();
+        let res = hc.finish_body(body);
+        if let Err(Error::Syntax { line, .. }) = &res {
+            let skipped: usize = text
+                .split_inclusive('\n')
+                .take(line.saturating_sub(hc.body_span.line))
+                .map(str::len)
+                .sum();
+            self.broken_body = Some(Span {
+                start: hc.body_span.start + skipped,
+                ..hc.body_span
+            });
+        }
+        res
     }
 
     fn parse(&mut self) -> Result<HttpRequest> {
         let mut hc = HttpRequest::new();
         let mut state = State::Url;
         let mut line = String::new();
+        let mut body = String::new();
 
         match self.r.fill_buf() {
             Ok(buf) => {
@@ -187,13 +666,20 @@ impl<BR: io::BufRead> Parser<BR> {
                         if hc.is_empty() {
                             return Err(Error::EOF);
                         }
+                        self.finish_body(&mut hc, body)?;
                         return Ok(hc);
                     }
                 }
                 Err(err) => return Err(err.into()),
             };
+            self.lineno += 1;
+            let start = self.offset;
+            self.offset += line.len();
 
-            let line = line.trim();
+            let raw = line.as_str();
+            let line = raw.trim();
+            let span = Span::of_line(raw, start, self.lineno);
+            self.line_span = span;
 
             match state {
                 State::Url => {
@@ -203,36 +689,66 @@ impl<BR: io::BufRead> Parser<BR> {
                     if line.starts_with("###") {
                         continue;
                     }
-                    if line.starts_with('#') {
-                        hc.comment.push_str(line);
+                    if let Some(comment) = comment_text(line) {
+                        if let Some(warning) = hc.push_comment(line, comment, span) {
+                            self.diagnostics.push(warning);
+                        }
                         continue;
                     }
-
-                    if let Err(err) = hc.parse_method_url_version(line) {
-                        return Err(Error::Other(err.into()));
+                    if line.starts_with('@') {
+                        if let Some(var) = Variable::parse(line, self.lineno) {
+                            self.variables.push(var);
+                            continue;
+                        }
                     }
+
+                    hc.parse_method_url_version(line, span)?;
+                    hc.span = span;
+                    hc.body_span = self.next_line();
                     state = State::Headers;
                 }
                 State::Headers => {
                     if line.is_empty() {
+                        hc.body_span = self.next_line();
                         state = State::Body;
                         continue;
                     }
+                    if line.starts_with("###") {
+                        return Ok(hc);
+                    }
+                    if let Some(comment) = comment_text(line) {
+                        if let Some(warning) = hc.push_comment(line, comment, span) {
+                            self.diagnostics.push(warning);
+                        }
+                        continue;
+                    }
+                    if hc.headers.is_empty() {
+                        if let Some(param) = query::continuation(line) {
+                            hc.url.push_query(&param);
+                            hc.span.end = span.end;
+                            continue;
+                        }
+                    }
+                    if raw.starts_with(is_ows) {
+                        match hc.headers.last_mut() {
+                            Some(header) => header.unfold(line, span)?,
+                            None => return Err(span.error(line, 0, Error::InvalidHeaderName)),
+                        }
+                        hc.span.end = span.end;
+                        continue;
+                    }
 
-                    match Header::parse(line.to_string()) {
-                        Ok(h) => hc.headers.push(h),
-                        Err(err) => return Err(Error::Other(err.into())),
-                    };
+                    let header = Header::parse(line, span)?;
+                    hc.span.end = span.end;
+                    hc.headers.push(header);
                 }
                 State::Body => {
-                    if line.is_empty() {
-                        continue;
-                    }
                     if line.starts_with("###") {
+                        self.finish_body(&mut hc, body)?;
                         return Ok(hc);
                     }
 
-                    hc.body.push_str(line);
+                    body.push_str(raw);
                 }
             }
         }
@@ -243,6 +759,28 @@ impl<BR: io::BufRead> Parser<BR> {
 mod tests {
     use super::*;
 
+    /// Span of the first occurrence of `text` in `input`.
+    fn span_of(input: &str, text: &str, line: usize, column: usize) -> Span {
+        let start = input.find(text).unwrap();
+        Span {
+            start,
+            end: start + text.len(),
+            line,
+            column,
+        }
+    }
+
+    /// Span from the start of `from` to the end of `to`.
+    fn span_between(input: &str, from: &str, to: &str, line: usize) -> Span {
+        let start = input.find(from).unwrap();
+        Span {
+            start,
+            end: input[start..].find(to).unwrap() + start + to.len(),
+            line,
+            column: 1,
+        }
+    }
+
     /*
     POST https://example.com/comments HTTP/1.1
     content-type: application/json
@@ -255,24 +793,34 @@ mod tests {
     #[test]
     fn parse_simplest() -> Result<()> {
         let input = r#"
-            POST https://example.com/comments HTTP/1.1
-            content-type: application/json
+POST https://example.com/comments HTTP/1.1
+content-type: application/json
 
-            {
-                "name": "sample",
-                "time": "Wed, 21 Oct 2015 18:27:50 GMT"
-            }
-        "#;
+{
+    "name": "sample",
+    "time": "Wed, 21 Oct 2015 18:27:50 GMT"
+}
+"#;
         let expected = HttpRequest {
             comment: String::new(),
-            method: "POST".to_owned(),
-            url: "https://example.com/comments".to_owned(),
-            version: "HTTP/1.1".to_owned(),
+            name: None,
+            settings: Settings::new(),
+            method: Method::Post,
+            url: "https://example.com/comments".parse()?,
+            version: HttpVersion::Http11,
+            spelling: Spelling::default(),
             headers: vec![Header {
                 name: "content-type".to_owned(),
                 value: "application/json".to_owned(),
+                span: span_of(input, "content-type: application/json", 3, 1),
             }],
-            body: r#"{"name": "sample","time": "Wed, 21 Oct 2015 18:27:50 GMT"}"#.to_owned(),
+            body: r#"{
+    "name": "sample",
+    "time": "Wed, 21 Oct 2015 18:27:50 GMT"
+}"#
+            .into(),
+            span: span_between(input, "POST", "\n}", 2),
+            body_span: span_between(input, "{", "\n}", 5),
         };
         let mut parser = Parser::new(io::Cursor::new(input));
 
@@ -288,45 +836,656 @@ mod tests {
     #[test]
     fn parse_multiple_2() -> Result<()> {
         let input = r#"
-            ###
-            # Comments
-            POST https://example.com/comments HTTP/1.1
-            content-type: application/json
-
-            {
-                "name": "sample",
-                "time": "Wed, 21 Oct 2015 18:27:50 GMT"
-            }
+###
+# Comments
+POST https://example.com/comments HTTP/1.1
+content-type: application/json
+
+{
+    "name": "sample",
+    "time": "Wed, 21 Oct 2015 18:27:50 GMT"
+}
 
 
 
-            ###
-            # GET without body and headers
-            POST https://example.com/ HTTP/2.0
-        "#;
+###
+# GET without body and headers
+POST https://example.com/ HTTP/2.0
+"#;
         let expect_request1 = HttpRequest {
             comment: "# Comments".to_owned(),
-            method: "POST".to_owned(),
-            url: "https://example.com/comments".to_owned(),
-            version: "HTTP/1.1".to_owned(),
+            name: None,
+            settings: Settings::new(),
+            method: Method::Post,
+            url: "https://example.com/comments".parse()?,
+            version: HttpVersion::Http11,
+            spelling: Spelling::default(),
             headers: vec![Header {
                 name: "content-type".to_owned(),
                 value: "application/json".to_owned(),
+                span: span_of(input, "content-type: application/json", 5, 1),
             }],
-            body: r#"{"name": "sample","time": "Wed, 21 Oct 2015 18:27:50 GMT"}"#.to_owned(),
+            body: r#"{
+    "name": "sample",
+    "time": "Wed, 21 Oct 2015 18:27:50 GMT"
+}"#
+            .into(),
+            span: span_between(input, "POST", "\n}", 4),
+            body_span: span_between(input, "{", "\n}", 7),
         };
         let expect_request2 = HttpRequest {
             comment: "# GET without body and headers".to_owned(),
-            method: "POST".to_owned(),
-            url: "https://example.com/".to_owned(),
-            version: "HTTP/2.0".to_owned(),
+            name: None,
+            settings: Settings::new(),
+            method: Method::Post,
+            url: "https://example.com/".parse()?,
+            version: HttpVersion::Http2,
+            spelling: Spelling {
+                method: None,
+                version: Some("HTTP/2.0".to_owned()),
+            },
             headers: Vec::new(),
-            body: String::new(),
+            body: Body::default(),
+            span: span_of(input, "POST https://example.com/ HTTP/2.0", 16, 1),
+            body_span: Span {
+                start: input.len(),
+                end: input.len(),
+                line: 17,
+                column: 1,
+            },
         };
 
-        let requests = parse(io::Cursor::new(input))?;
+        let document = parse(io::Cursor::new(input))?;
+
+        assert_eq!(document.requests, vec![expect_request1, expect_request2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_variables() -> Result<()> {
+        let input = r#"
+@host = example.com
+@baseUrl = https://{{host}}
+
+###
+GET {{baseUrl}}/users/{{id}} HTTP/1.1
+authorization: Bearer {{token}}
+
+###
+@token = secret
+"#;
+
+        let document = parse(io::Cursor::new(input))?;
+        let names: Vec<_> = document.variables.iter().map(|v| v.name.as_str()).collect();
+        let unresolved = document.requests[0].resolve(document.variables.as_slice());
+        let vars = (
+            document.variables.as_slice(),
+            std::collections::HashMap::from([("id".to_owned(), "42".to_owned())]),
+        );
+        let resolved = document.requests[0].resolve(&vars)?;
+
+        assert_eq!(names, ["host", "baseUrl", "token"]);
+        assert!(matches!(
+            unresolved,
+            Err(Error::UndefinedVariable { ref name, line: 6 }) if name == "id"
+        ));
+        assert_eq!(resolved.url.as_str(), "https://example.com/users/42");
+        assert_eq!(resolved.headers[0].value, "Bearer secret");
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_body_verbatim() -> Result<()> {
+        let input = "POST https://example.com/ HTTP/1.1
+content-type: text/plain
+
+  indented line
+
+# not a comment
+last line  \t
+###
+POST https://example.com/xml HTTP/1.1
+content-type: application/xml
+
+<?xml version=\"1.0\"?>
+<note>
+    <to>Tove</to>
+</note>
+
+";
+
+        let document = parse(io::Cursor::new(input))?;
+        let bodies: Vec<_> = document.requests.iter().map(|r| r.body.as_text()).collect();
+
+        assert_eq!(
+            bodies,
+            [
+                Some("  indented line\n\n# not a comment\nlast line"),
+                Some("<?xml version=\"1.0\"?>\n<note>\n    <to>Tove</to>\n</note>"),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_error_position() {
+        let input = "###\n\n  POST\n";
+
+        let err = parse(io::Cursor::new(input)).unwrap_err();
+
+        assert!(matches!(
+            err,
+            Error::Syntax { line: 3, column: 7, ref source } if matches!(**source, Error::NoUrl)
+        ));
+        assert_eq!(
+            err.to_string(),
+            "couldn't parse http url at line 3, column 7"
+        );
+    }
+
+    #[test]
+    fn parse_request_line() -> Result<()> {
+        let input = "get https://example.com/ HTTP/2.0
+###
+PURGE {{baseUrl}}/cache
+";
+
+        let document = parse(io::Cursor::new(input))?;
+
+        assert_eq!(document.requests[0].method, Method::Get);
+        assert_eq!(document.requests[0].version, HttpVersion::Http2);
+        assert_eq!(document.requests[0].method_text(), "get");
+        assert_eq!(document.requests[0].version_text(), "HTTP/2.0");
+        assert_eq!(document.requests[1].spelling, Spelling::default());
+        assert_eq!(document.requests[1].version_text(), "HTTP/1.1");
+        assert_eq!(document.requests[0].url.authority(), Some("example.com"));
+        assert_eq!(
+            document.requests[1].method,
+            Method::Extension("PURGE".to_owned())
+        );
+        assert_eq!(document.requests[1].url.scheme(), None);
+
+        let column = |input: &str| match parse(io::Cursor::new(input)) {
+            Err(Error::Syntax { column, source, .. }) => (column, source.to_string()),
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(
+            column("GE(T https://example.com/"),
+            (3, "invalid method".to_owned())
+        );
+        assert_eq!(column("GET example.com/"), (5, "invalid URL".to_owned()));
+        assert_eq!(column("GET https:///path"), (13, "invalid URL".to_owned()));
+        assert_eq!(
+            column("GET https://example.com/a|b"),
+            (26, "invalid URL".to_owned())
+        );
+        assert_eq!(
+            column("GET https://example.com/ HTTP/4"),
+            (26, "unsupported HTTP version".to_owned())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_recovering_skips_broken() -> Result<()> {
+        let input = "GET https://example.com/1
+bad header
+
+body
+###
+GET https://example.com/2
+###
+POST
+content-type: text/plain
+";
+
+        let document = parse_recovering(io::Cursor::new(input))?;
+        let urls: Vec<_> = document.requests.iter().map(|r| r.url.as_str()).collect();
+
+        assert_eq!(urls, ["https://example.com/2"]);
+        assert_eq!(
+            document.diagnostics,
+            [
+                Diagnostic {
+                    severity: Severity::Error,
+                    span: Span {
+                        column: 11,
+                        ..span_between(input, "bad header", "body", 2)
+                    },
+                    message: "invalid header value".to_owned(),
+                },
+                Diagnostic {
+                    severity: Severity::Error,
+                    span: Span {
+                        column: 5,
+                        ..span_between(input, "POST", "text/plain", 8)
+                    },
+                    message: "couldn't parse http url".to_owned(),
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_recovering_broken_body() -> Result<()> {
+        let input = "POST https://example.com/upload
+content-type: multipart/form-data; boundary=b
+
+--b
+Content-Disposition form-data
+--b--
+###
+GET https://example.com/valid
+";
+
+        let document = parse_recovering(io::Cursor::new(input))?;
+        let urls: Vec<_> = document.requests.iter().map(|r| r.url.as_str()).collect();
+
+        assert_eq!(urls, ["https://example.com/valid"]);
+        assert_eq!(document.diagnostics.len(), 1);
+        assert_eq!(
+            document.diagnostics[0].span,
+            Span {
+                column: 30,
+                ..span_between(input, "Content-Disposition", "--b--", 5)
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_directives() -> Result<()> {
+        let input = "# @name login
+# @no-redirect
+// @timeout 2 m
+# @connection-timeout=500ms
+# @retry 3
+# @no-cookie-jars
+GET https://example.com/
+# @timeout soon
+";
+
+        let document = parse(io::Cursor::new(input))?;
+        let req = &document.requests[0];
+
+        assert_eq!(req.name.as_deref(), Some("login"));
+        assert!(req.has(Directive::NoRedirect));
+        assert!(!req.has(Directive::NoCookieJar));
+        assert_eq!(req.timeout(), Some(Duration::from_secs(120)));
+        assert_eq!(req.connection_timeout(), Some(Duration::from_millis(500)));
+        assert_eq!(
+            req.comment,
+            "# @retry 3\n# @no-cookie-jars\n# @timeout soon"
+        );
+        let warnings: Vec<_> = document
+            .diagnostics
+            .iter()
+            .map(|d| (d.severity, d.span.line, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                (
+                    Severity::Warning,
+                    6,
+                    "unknown directive @no-cookie-jars, did you mean @no-cookie-jar?"
+                ),
+                (
+                    Severity::Warning,
+                    8,
+                    "invalid duration `soon` for @timeout, expected a number with an optional ms, s or m unit"
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+
+    fn parse_header(line: &str) -> Result<Header> {
+        let span = Span {
+            start: 0,
+            end: line.len(),
+            line: 1,
+            column: 1,
+        };
+        Header::parse(line, span)
+    }
+
+    #[test]
+    fn header_value_with_colon() -> Result<()> {
+        let header = parse_header("Authorization: Bearer a: b")?;
+
+        assert_eq!(header.name, "Authorization");
+        assert_eq!(header.value, "Bearer a: b");
+
+        Ok(())
+    }
+
+    #[test]
+    fn header_optional_whitespace() -> Result<()> {
+        let compact = parse_header("Name:value")?;
+        let padded = parse_header("Name: \t value \t")?;
+        let empty = parse_header("Name:")?;
+
+        assert_eq!(compact.value, "value");
+        assert_eq!(padded.value, "value");
+        assert_eq!(empty.value, "");
+
+        Ok(())
+    }
+
+    #[test]
+    fn header_malformed() -> Result<()> {
+        let no_colon = parse_header("bad header");
+        let bad_name = parse_header("Bad Name: value");
+        let empty_name = parse_header(": value");
+        let placeholder = parse_header("X-{{name}}: value")?;
+
+        assert!(matches!(
+            no_colon,
+            Err(Error::Syntax { column: 11, ref source, .. })
+                if matches!(**source, Error::InvalidHeaderValue)
+        ));
+        assert!(matches!(
+            bad_name,
+            Err(Error::Syntax { column: 4, ref source, .. })
+                if matches!(**source, Error::InvalidHeaderName)
+        ));
+        assert!(matches!(
+            empty_name,
+            Err(Error::Syntax { column: 1, ref source, .. })
+                if matches!(**source, Error::InvalidHeaderName)
+        ));
+        assert_eq!(placeholder.name, "X-{{name}}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn header_obs_fold() -> Result<()> {
+        let input = "GET https://example.com/ HTTP/1.1
+X-Long: first
+  second
+\tthird
+Accept: */*
+";
+
+        let document = parse(io::Cursor::new(input))?;
+        let headers = &document.requests[0].headers;
+
+        assert_eq!(headers[0].value, "first second third");
+        assert_eq!(headers[0].span, span_between(input, "X-Long", "third", 2));
+        assert_eq!(headers[1].name, "Accept");
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_request_name() -> Result<()> {
+        let input = "# @name login
+# Log in first
+POST https://example.com/login HTTP/1.1
+###
+#@name=me
+GET https://example.com/me HTTP/1.1
+###
+# @names are not names
+GET https://example.com/ HTTP/1.1
+";
+
+        let document = parse(io::Cursor::new(input))?;
+        let names: Vec<_> = document
+            .requests
+            .iter()
+            .map(|r| r.name.as_deref())
+            .collect();
+
+        assert_eq!(names, [Some("login"), Some("me"), None]);
+        assert_eq!(document.requests[0].comment, "# Log in first");
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_comments() -> Result<()> {
+        let input = "# Create a user
+// with a token
+// @name create
+POST https://example.com/users HTTP/1.1
+Content-Type: application/json
+# the token expires daily
+// Authorization: Bearer old
+Authorization: Bearer {{token}}
+
+{}
+";
+
+        let document = parse(io::Cursor::new(input))?;
+        let request = &document.requests[0];
+
+        assert_eq!(request.name.as_deref(), Some("create"));
+        assert_eq!(
+            request.comment,
+            "# Create a user\n// with a token\n# the token expires daily\n// Authorization: Bearer old"
+        );
+        assert_eq!(request.headers.len(), 2);
+        assert_eq!(request.headers[1].name, "Authorization");
+        assert_eq!(request.body, Body::from("{}"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_body_file() -> Result<()> {
+        let input = "POST https://example.com/ HTTP/1.1
+
+< ./payload.json
+###
+POST https://example.com/ HTTP/1.1
+
+<@ ./template.xml
+###
+POST https://example.com/ HTTP/1.1
+
+<not-a-file/>
+";
+        let dir = std::env::temp_dir().join("httpfile-parse-body-file");
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("template.xml"), "<id>{{id}}</id>\n")?;
+
+        let mut document = parse(io::Cursor::new(input))?;
+        document.rebase(&dir);
+        let vars = std::collections::HashMap::from([("id".to_owned(), "42".to_owned())]);
+        let resolved = document.requests[1].resolve(&vars)?;
+
+        assert_eq!(
+            document.requests[0].body,
+            Body::File {
+                path: dir.join("./payload.json"),
+                substitute: false,
+            }
+        );
+        assert_eq!(resolved.body, Body::from("<id>42</id>\n"));
+        assert_eq!(document.requests[2].body, Body::from("<not-a-file/>"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_multipart() -> Result<()> {
+        let input = r#"POST https://example.com/upload HTTP/1.1
+Content-Type: multipart/form-data; boundary="----Boundary"
+
+------Boundary
+Content-Disposition: form-data; name="text"
+
+title
+with two lines
+------Boundary
+Content-Disposition: form-data; name="image"; filename="1.png"
+Content-Type: image/png
+
+< ./1.png
+------Boundary--
+"#;
+
+        let document = parse(io::Cursor::new(input))?;
+        let multipart = match &document.requests[0].body {
+            Body::Multipart(multipart) => multipart,
+            body => panic!("expected multipart, got {:?}", body),
+        };
+
+        assert_eq!(multipart.boundary, "----Boundary");
+        assert_eq!(multipart.parts.len(), 2);
+        assert_eq!(multipart.parts[0].name(), Some("text"));
+        assert_eq!(multipart.parts[0].body, Body::from("title\nwith two lines"));
+        assert_eq!(multipart.parts[1].name(), Some("image"));
+        assert_eq!(multipart.parts[1].file_name(), Some("1.png"));
+        assert_eq!(multipart.parts[1].headers[1].span.line, 11);
+        assert_eq!(
+            multipart.parts[1].body,
+            Body::File {
+                path: PathBuf::from("./1.png"),
+                substitute: false,
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_query_continuation() -> Result<()> {
+        let input = "GET https://example.com/search?lang=en
+    &q=rust http client
+    &page={{page}}
+    &tag=a&b
+    &flag
+Accept: */*
+";
+
+        let document = parse(io::Cursor::new(input))?;
+        let req = &document.requests[0];
+
+        assert_eq!(
+            req.url.as_str(),
+            "https://example.com/search?lang=en&q=rust%20http%20client&page={{page}}&tag=a%26b&flag"
+        );
+        assert_eq!(
+            req.query(),
+            [
+                ("lang".to_owned(), "en".to_owned()),
+                ("q".to_owned(), "rust http client".to_owned()),
+                ("page".to_owned(), "{{page}}".to_owned()),
+                ("tag".to_owned(), "a&b".to_owned()),
+                ("flag".to_owned(), String::new()),
+            ]
+        );
+        assert_eq!(req.headers.len(), 1);
+        assert_eq!(req.span, span_between(input, "GET", "*/*", 1));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn to_reqwest_version() -> Result<()> {
+        let input = "GET https://example.com/ HTTP/1.0
+###
+# @timeout 5
+GET https://example.com/
+###
+GET http://example.com/ HTTP/2
+###
+GET https://example.com/ HTTP/3
+###
+GET https://example.com/ HTTP/0.9
+";
+        let document = parse(io::Cursor::new(input))?;
+        let client = reqwest::Client::new();
+
+        let sent = document.requests[..3]
+            .iter()
+            .map(|req| req.to_reqwest(&client))
+            .collect::<Result<Vec<_>>>()?;
+
+        let versions: Vec<_> = sent.iter().map(|req| req.version()).collect();
+        assert_eq!(
+            versions,
+            [
+                reqwest::Version::HTTP_10,
+                reqwest::Version::HTTP_11,
+                reqwest::Version::HTTP_2
+            ]
+        );
+        assert_eq!(sent[1].timeout(), Some(&Duration::from_secs(5)));
+        for req in &document.requests[3..] {
+            assert!(matches!(
+                req.to_reqwest(&client),
+                Err(Error::UnsupportedVersion(version)) if version == req.version
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn url_components() -> Result<()> {
+        let url: Url = "https://user@example.com:8080/a/b?x=1&y=#top?z".parse()?;
+
+        assert_eq!(url.scheme(), Some("https"));
+        assert_eq!(url.authority(), Some("user@example.com:8080"));
+        assert_eq!(url.path(), Some("/a/b"));
+        assert_eq!(url.query(), Some("x=1&y="));
+        assert_eq!(url.fragment(), Some("top?z"));
+
+        let url: Url = "{{host}}/users?page=2".parse()?;
+
+        assert_eq!(url.scheme(), None);
+        assert_eq!(url.authority(), None);
+        assert_eq!(url.path(), None);
+        assert_eq!(url.query(), Some("page=2"));
+        assert_eq!(url.fragment(), None);
+
+        let url: Url = "http://example.com".parse()?;
+
+        assert_eq!(url.authority(), Some("example.com"));
+        assert_eq!(url.path(), Some(""));
+        assert_eq!(url.query(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_query_continuation_fragment() -> Result<()> {
+        let input = "GET https://example.com/p#frag
+  &a=1
+###
+GET https://example.com/p#frag?x
+  &a=1
+";
+
+        let document = parse(io::Cursor::new(input))?;
+        let urls: Vec<_> = document.requests.iter().map(|r| r.url.as_str()).collect();
 
-        assert_eq!(requests, vec![expect_request1, expect_request2]);
+        assert_eq!(
+            urls,
+            [
+                "https://example.com/p?a=1#frag",
+                "https://example.com/p?a=1#frag?x"
+            ]
+        );
+        assert_eq!(document.requests[1].url.query(), Some("a=1"));
+        assert_eq!(
+            document.requests[1].query(),
+            [("a".to_owned(), "1".to_owned())]
+        );
 
         Ok(())
     }
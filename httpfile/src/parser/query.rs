@@ -0,0 +1,86 @@
+/// Characters that are kept as is in a query key or value.
+fn is_query_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-._~!$'()*,;:@/?".contains(c)
+}
+
+/// Percent-encodes a query key or value, keeping `{{placeholders}}` and `%XX` escapes.
+pub(crate) fn encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("{{") {
+            if let Some(end) = rest.find("}}") {
+                out.push_str(&rest[..end + 2]);
+                rest = &rest[end + 2..];
+                continue;
+            }
+        }
+        if c == '%' && rest.len() >= 3 && rest[1..3].chars().all(|c| c.is_ascii_hexdigit()) {
+            out.push_str(&rest[..3]);
+            rest = &rest[3..];
+            continue;
+        }
+
+        if is_query_char(c) {
+            out.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                out.push_str(&format!("%{:02X}", b));
+            }
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+
+    out
+}
+
+/// Decodes `%XX` escapes, invalid UTF-8 is replaced.
+pub(crate) fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = text
+            .get(i + 1..i + 3)
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Turns a `?key=value` or `&key=value` continuation line into an encoded `key=value`.
+pub(crate) fn continuation(line: &str) -> Option<String> {
+    let param = line
+        .strip_prefix('?')
+        .or_else(|| line.strip_prefix('&'))?
+        .trim();
+    let encoded = match param.split_once('=') {
+        Some((key, value)) => format!("{}={}", encode(key.trim()), encode(value.trim())),
+        None => encode(param),
+    };
+    Some(encoded)
+}
+
+/// Decoded `key=value` pairs of a query.
+pub(crate) fn pairs(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => (decode(key), decode(value)),
+            None => (decode(pair), String::new()),
+        })
+        .collect()
+}
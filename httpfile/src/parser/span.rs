@@ -0,0 +1,43 @@
+use super::Error;
+
+/// Location of a piece of the source text.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+    /// Byte offset of the first character.
+    pub start: usize,
+    /// Byte offset right after the last character.
+    pub end: usize,
+    /// Line of the first character, starting from 1.
+    pub line: usize,
+    /// Column of the first character in characters, starting from 1.
+    pub column: usize,
+}
+
+impl Span {
+    /// Span of `raw` without surrounding whitespace, `raw` starts at byte `start`.
+    pub(crate) fn of_line(raw: &str, start: usize, line: usize) -> Span {
+        let trimmed = raw.trim_start();
+        let indent = &raw[..raw.len() - trimmed.len()];
+        let start = start + indent.len();
+
+        Span {
+            start,
+            end: start + trimmed.trim_end().len(),
+            line,
+            column: indent.chars().count() + 1,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Wraps `err` with the position of byte `offset` of `text`, which starts at this span.
+    pub(crate) fn error(&self, text: &str, offset: usize, err: Error) -> Error {
+        Error::Syntax {
+            line: self.line,
+            column: self.column + text[..offset].chars().count(),
+            source: Box::new(err),
+        }
+    }
+}
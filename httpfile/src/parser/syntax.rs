@@ -0,0 +1,245 @@
+use std::fmt;
+
+use super::{comment_text, is_ows, query, Span};
+use crate::variables::Variable;
+
+/// What a line of a .http file is, classified the same way the parser reads it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LineKind {
+    Blank,
+    /// `###`, optionally followed by a title.
+    Separator,
+    /// `#` or `//` comment, including `# @name` and other directives.
+    Comment,
+    /// `@name = value`.
+    Variable,
+    /// `METHOD url [version]`.
+    RequestLine,
+    /// `?key=value` or `&key=value` continuing the url.
+    Query,
+    Header,
+    /// Starts with whitespace and continues the previous header.
+    Folded,
+    /// Any line after the blank line that ends the headers.
+    Body,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Line {
+    pub kind: LineKind,
+    /// Source text including the line break, if any.
+    pub text: String,
+    /// Position of the text without surrounding whitespace.
+    pub span: Span,
+}
+
+impl Line {
+    /// Text without the line break.
+    pub fn content(&self) -> &str {
+        self.text.trim_end_matches(['\r', '\n'])
+    }
+}
+
+/// Lines from a `###` separator up to the next one.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Block {
+    /// `None` for the lines at the start of the file.
+    pub separator: Option<Line>,
+    /// Blank lines, comments and variables before the request line.
+    pub leading: Vec<Line>,
+    pub request: Option<RequestSyntax>,
+}
+
+impl Block {
+    pub fn lines(&self) -> impl Iterator<Item = &Line> {
+        let request = self.request.iter().flat_map(RequestSyntax::lines);
+        self.separator.iter().chain(&self.leading).chain(request)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RequestSyntax {
+    pub request_line: Line,
+    /// Query, header, folded and comment lines after the request line.
+    pub headers: Vec<Line>,
+    /// Blank line between the headers and the body.
+    pub blank: Option<Line>,
+    pub body: Vec<Line>,
+}
+
+impl RequestSyntax {
+    pub fn lines(&self) -> impl Iterator<Item = &Line> {
+        std::iter::once(&self.request_line)
+            .chain(&self.headers)
+            .chain(&self.blank)
+            .chain(&self.body)
+    }
+
+    /// Value of the first header called `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .filter(|line| line.kind == LineKind::Header)
+            .filter_map(|line| line.content().trim().split_once(':'))
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim_matches(is_ows))
+    }
+}
+
+/// Lossless syntax tree of a .http file, writing it gives back the source byte for byte.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct SyntaxTree {
+    pub blocks: Vec<Block>,
+}
+
+impl SyntaxTree {
+    pub fn lines(&self) -> impl Iterator<Item = &Line> {
+        self.blocks.iter().flat_map(Block::lines)
+    }
+}
+
+impl fmt::Display for SyntaxTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in self.lines() {
+            f.write_str(&line.text)?;
+        }
+        Ok(())
+    }
+}
+
+/// Splits `source` into blocks and lines without validating them.
+pub fn parse_syntax(source: &str) -> SyntaxTree {
+    let mut blocks = vec![Block::default()];
+    let mut offset = 0;
+
+    for (i, text) in source.split_inclusive('\n').enumerate() {
+        let line = Line {
+            kind: LineKind::Blank,
+            text: text.to_string(),
+            span: Span::of_line(text, offset, i + 1),
+        };
+        offset += text.len();
+
+        let trimmed = text.trim();
+        if trimmed.starts_with("###") {
+            blocks.push(Block {
+                separator: Some(Line {
+                    kind: LineKind::Separator,
+                    ..line
+                }),
+                ..Block::default()
+            });
+            continue;
+        }
+
+        let block = blocks.last_mut().expect("there is always a block");
+        let req = match &mut block.request {
+            Some(req) => req,
+            None => {
+                let kind = if trimmed.is_empty() {
+                    LineKind::Blank
+                } else if comment_text(trimmed).is_some() {
+                    LineKind::Comment
+                } else if trimmed.starts_with('@') && Variable::parse(trimmed, i + 1).is_some() {
+                    LineKind::Variable
+                } else {
+                    block.request = Some(RequestSyntax {
+                        request_line: Line {
+                            kind: LineKind::RequestLine,
+                            ..line
+                        },
+                        headers: Vec::new(),
+                        blank: None,
+                        body: Vec::new(),
+                    });
+                    continue;
+                };
+                block.leading.push(Line { kind, ..line });
+                continue;
+            }
+        };
+
+        if req.blank.is_some() {
+            req.body.push(Line {
+                kind: LineKind::Body,
+                ..line
+            });
+            continue;
+        }
+        let no_headers = !req.headers.iter().any(|l| l.kind == LineKind::Header);
+        let kind = if trimmed.is_empty() {
+            req.blank = Some(line);
+            continue;
+        } else if comment_text(trimmed).is_some() {
+            LineKind::Comment
+        } else if no_headers && query::continuation(trimmed).is_some() {
+            LineKind::Query
+        } else if text.starts_with(is_ows) {
+            LineKind::Folded
+        } else {
+            LineKind::Header
+        };
+        req.headers.push(Line { kind, ..line });
+    }
+
+    SyntaxTree { blocks }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn classify_lines() {
+        let source = "@host = example.com\r
+# list users\r
+\r
+GET https://{{host}}/users\r
+  ?page=2\r
+Accept: application/json\r
+X-Long: first\r
+  second\r
+\r
+{}\r
+###\r
+### Create\r
+POST https://{{host}}/users";
+
+        let tree = parse_syntax(source);
+        let kinds: Vec<_> = tree.lines().map(|l| l.kind).collect();
+
+        assert_eq!(tree.to_string(), source);
+        assert_eq!(tree.blocks.len(), 3);
+        assert_eq!(
+            kinds,
+            [
+                LineKind::Variable,
+                LineKind::Comment,
+                LineKind::Blank,
+                LineKind::RequestLine,
+                LineKind::Query,
+                LineKind::Header,
+                LineKind::Header,
+                LineKind::Folded,
+                LineKind::Blank,
+                LineKind::Body,
+                LineKind::Separator,
+                LineKind::Separator,
+                LineKind::RequestLine,
+            ]
+        );
+        let req = tree.blocks[0].request.as_ref().unwrap();
+        assert_eq!(req.header("accept"), Some("application/json"));
+        assert_eq!(req.request_line.content(), "GET https://{{host}}/users");
+        assert_eq!(req.request_line.span.line, 4);
+    }
+
+    proptest! {
+        #[test]
+        fn lossless(source in "([ -~\t]{0,30}(\n|\r\n)?){0,10}") {
+            prop_assert_eq!(parse_syntax(&source).to_string(), source);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use super::Error;
+
+/// Request target as written, may contain `{{name}}` placeholders.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Url {
+    text: String,
+    parts: Parts,
+}
+
+/// Byte ranges of the components of the text, split once when it's set.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct Parts {
+    /// End of the scheme, only known when the URL doesn't start with a placeholder.
+    scheme: Option<usize>,
+    authority: Option<Range<usize>>,
+    path: Option<Range<usize>>,
+    query: Option<Range<usize>>,
+    /// Start of the fragment, after the `#`.
+    fragment: Option<usize>,
+}
+
+impl Parts {
+    fn split(text: &str) -> Parts {
+        let fragment = text.find('#');
+        let end = fragment.unwrap_or(text.len());
+        let query = text[..end].find('?').map(|i| i + 1..end);
+        let end = query.as_ref().map_or(end, |query| query.start - 1);
+
+        let scheme = text
+            .split_once("://")
+            .map(|(scheme, _)| scheme)
+            .filter(|scheme| valid_scheme(scheme))
+            .map(str::len);
+        let (authority, path) = match scheme {
+            Some(scheme) => {
+                let start = scheme + 3;
+                let host_end = text[start..end].find('/').map_or(end, |i| start + i);
+                (Some(start..host_end), Some(host_end..end))
+            }
+            None => (None, None),
+        };
+
+        Parts {
+            scheme,
+            authority,
+            path,
+            query,
+            fragment: fragment.map(|i| i + 1),
+        }
+    }
+}
+
+impl Url {
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// `https` of `https://example.com/`, `None` when it isn't known before resolving.
+    pub fn scheme(&self) -> Option<&str> {
+        self.parts.scheme.map(|end| &self.text[..end])
+    }
+
+    /// `user@example.com:8080` of `https://user@example.com:8080/path`.
+    pub fn authority(&self) -> Option<&str> {
+        self.parts.authority.clone().map(|range| &self.text[range])
+    }
+
+    /// `/path` of `https://example.com/path?a=1`, empty when there's no path.
+    pub fn path(&self) -> Option<&str> {
+        self.parts.path.clone().map(|range| &self.text[range])
+    }
+
+    /// Text after `?` without the fragment.
+    pub fn query(&self) -> Option<&str> {
+        self.parts.query.clone().map(|range| &self.text[range])
+    }
+
+    /// Text after `#`.
+    pub fn fragment(&self) -> Option<&str> {
+        self.parts.fragment.map(|start| &self.text[start..])
+    }
+
+    /// Byte offset of the first problem in `text`.
+    pub(crate) fn invalid_char(text: &str) -> Option<usize> {
+        if text.is_empty() {
+            return Some(0);
+        }
+        let mut i = 0;
+        while i < text.len() {
+            let rest = &text[i..];
+            if rest.starts_with("{{") {
+                if let Some(end) = rest.find("}}") {
+                    i += end + 2;
+                    continue;
+                }
+            }
+
+            let c = rest.chars().next()?;
+            if c.is_control() || c.is_whitespace() || "\"<>\\^`{|}".contains(c) {
+                return Some(i);
+            }
+            i += c.len_utf8();
+        }
+
+        if text.starts_with("{{") {
+            return None;
+        }
+        let (scheme, rest) = match text.split_once("://") {
+            Some(parts) => parts,
+            None => return Some(0),
+        };
+        if !valid_scheme(scheme) {
+            return Some(0);
+        }
+        if rest.is_empty() || rest.starts_with(['/', '?', '#']) {
+            return Some(scheme.len() + 3);
+        }
+
+        None
+    }
+
+    /// Appends an encoded `key=value` to the query, before the fragment if there is one.
+    pub(crate) fn push_query(&mut self, param: &str) {
+        if param.is_empty() {
+            return;
+        }
+        let (at, separator) = match &self.parts.query {
+            None => (self.parts.fragment.map_or(self.text.len(), |i| i - 1), "?"),
+            Some(query) if query.is_empty() || self.text[..query.end].ends_with('&') => {
+                (query.end, "")
+            }
+            Some(query) => (query.end, "&"),
+        };
+        self.text.insert_str(at, &format!("{}{}", separator, param));
+        self.parts = Parts::split(&self.text);
+    }
+}
+
+fn valid_scheme(scheme: &str) -> bool {
+    let mut chars = scheme.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+}
+
+impl FromStr for Url {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Url, Error> {
+        if Url::invalid_char(text).is_some() {
+            return Err(Error::InvalidURL);
+        }
+        Ok(Url {
+            text: text.to_string(),
+            parts: Parts::split(text),
+        })
+    }
+}
+
+impl fmt::Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{HttpVersion, Resolver};
+
+/// Response received for a request, kept to be referenced from later requests.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Response {
+    /// Version the server answered with.
+    pub version: HttpVersion,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Evaluates `*`, a JSONPath (`$.token`) or an XPath (`//token`) against the body.
+    pub fn select(&self, path: &str) -> Option<String> {
+        if path == "*" {
+            Some(self.body.clone())
+        } else if path.starts_with('$') {
+            select_json(&self.body, path)
+        } else if path.starts_with('/') {
+            select_xml(&self.body, path)
+        } else {
+            None
+        }
+    }
+}
+
+fn select_json(body: &str, path: &str) -> Option<String> {
+    let json: Value = serde_json::from_str(body).ok()?;
+    let found = jsonpath_lib::select(&json, path).ok()?;
+    match found.first()? {
+        Value::String(s) => Some(s.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+fn select_xml(body: &str, path: &str) -> Option<String> {
+    let package = sxd_document::parser::parse(body).ok()?;
+    let document = package.as_document();
+    let value = sxd_xpath::evaluate_xpath(&document, path).ok()?;
+    Some(value.string())
+}
+
+/// Latest responses of named requests, resolves `{{name.response.body.$.path}}`.
+#[derive(Debug, Clone, Default)]
+pub struct Responses {
+    responses: HashMap<String, Response>,
+}
+
+impl Responses {
+    pub fn insert(&mut self, name: String, response: Response) {
+        self.responses.insert(name, response);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Response> {
+        self.responses.get(name)
+    }
+}
+
+impl Resolver for Responses {
+    fn resolve(&self, name: &str) -> Option<String> {
+        let mut parts = name.splitn(4, '.');
+        let request = parts.next()?;
+        if parts.next()? != "response" {
+            return None;
+        }
+        let part = parts.next()?;
+        let path = parts.next()?;
+
+        let response = self.get(request)?;
+        match part {
+            "body" => response.select(path),
+            "headers" => response.header(path).map(String::from),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn responses(content_type: &str, body: &str) -> Responses {
+        let mut responses = Responses::default();
+        responses.insert(
+            "login".to_owned(),
+            Response {
+                version: HttpVersion::Http11,
+                status: 200,
+                headers: vec![
+                    ("content-type".to_owned(), content_type.to_owned()),
+                    ("location".to_owned(), "/users/1".to_owned()),
+                ],
+                body: body.to_owned(),
+            },
+        );
+        responses
+    }
+
+    #[test]
+    fn resolve_json_and_headers() {
+        let responses = responses(
+            "application/json",
+            r#"{"token": "abc", "user": {"id": 1, "roles": ["admin"]}}"#,
+        );
+
+        assert_eq!(
+            responses.resolve("login.response.body.$.token").as_deref(),
+            Some("abc")
+        );
+        assert_eq!(
+            responses
+                .resolve("login.response.body.$.user.id")
+                .as_deref(),
+            Some("1")
+        );
+        assert_eq!(
+            responses
+                .resolve("login.response.body.$.user.roles[0]")
+                .as_deref(),
+            Some("admin")
+        );
+        assert_eq!(
+            responses
+                .resolve("login.response.headers.Location")
+                .as_deref(),
+            Some("/users/1")
+        );
+        assert_eq!(responses.resolve("login.response.body.$.missing"), None);
+        assert_eq!(responses.resolve("other.response.body.*"), None);
+        assert_eq!(responses.resolve("token"), None);
+    }
+
+    #[test]
+    fn resolve_xml() {
+        let responses = responses("application/xml", "<login><token>abc</token></login>");
+
+        assert_eq!(
+            responses.resolve("login.response.body.//token").as_deref(),
+            Some("abc")
+        );
+        assert_eq!(
+            responses.resolve("login.response.body.*").as_deref(),
+            Some("<login><token>abc</token></login>")
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Local, Months, Offset, SecondsFormat, TimeZone, Utc};
+use rand::Rng;
+
+use super::Resolver;
+use crate::{Error, Result};
+
+/// File with `NAME=value` lines read by `{{$dotenv NAME}}`.
+pub const DOTENV_FILE: &str = ".env";
+
+/// System variables such as `{{$uuid}}`, create a new one for each request sent.
+#[derive(Debug, Clone)]
+pub struct DynamicVariables {
+    now: DateTime<Utc>,
+    dotenv: HashMap<String, String>,
+}
+
+impl DynamicVariables {
+    /// Reads `.env` from `dir`, a missing file is treated as empty.
+    pub fn load(dir: &Path) -> Result<DynamicVariables> {
+        let path = dir.join(DOTENV_FILE);
+        let dotenv = match fs::read_to_string(&path) {
+            Ok(text) => parse_dotenv(&text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(source) => return Err(Error::ReadFile { path, source }),
+        };
+
+        Ok(DynamicVariables {
+            now: Utc::now(),
+            dotenv,
+        })
+    }
+}
+
+impl Resolver for DynamicVariables {
+    fn resolve(&self, name: &str) -> Option<String> {
+        let args = split_args(name.strip_prefix('$')?)?;
+        let (name, args) = args.split_first()?;
+
+        match (name.as_str(), args) {
+            ("uuid" | "guid", []) => Some(uuid::Uuid::new_v4().to_string()),
+            ("randomInt", [min, max]) => {
+                let (min, max): (i64, i64) = (min.parse().ok()?, max.parse().ok()?);
+                (min < max).then(|| rand::thread_rng().gen_range(min..max).to_string())
+            }
+            ("timestamp", offset) => Some(self.offset(offset)?.timestamp().to_string()),
+            ("datetime", [format, offset @ ..]) => format_datetime(self.offset(offset)?, format),
+            ("localDatetime", [format, offset @ ..]) => {
+                format_datetime(self.offset(offset)?.with_timezone(&Local), format)
+            }
+            ("processEnv", [var]) => env::var(var).ok(),
+            ("dotenv", [var]) => self.dotenv.get(var).cloned(),
+            _ => None,
+        }
+    }
+}
+
+impl DynamicVariables {
+    /// Current time moved by `[amount, unit]`, e.g. `-1 d`.
+    fn offset(&self, offset: &[String]) -> Option<DateTime<Utc>> {
+        let (amount, unit) = match offset {
+            [] => return Some(self.now),
+            [amount, unit] => (amount.parse::<i64>().ok()?, unit.as_str()),
+            _ => return None,
+        };
+
+        match unit {
+            "y" => add_months(self.now, amount.checked_mul(12)?),
+            "M" => add_months(self.now, amount),
+            "w" => self.now.checked_add_signed(Duration::try_weeks(amount)?),
+            "d" => self.now.checked_add_signed(Duration::try_days(amount)?),
+            "h" => self.now.checked_add_signed(Duration::try_hours(amount)?),
+            "m" => self.now.checked_add_signed(Duration::try_minutes(amount)?),
+            "s" => self.now.checked_add_signed(Duration::try_seconds(amount)?),
+            "ms" => self
+                .now
+                .checked_add_signed(Duration::try_milliseconds(amount)?),
+            _ => None,
+        }
+    }
+}
+
+fn add_months(time: DateTime<Utc>, months: i64) -> Option<DateTime<Utc>> {
+    let n = Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+    if months < 0 {
+        time.checked_sub_months(n)
+    } else {
+        time.checked_add_months(n)
+    }
+}
+
+/// `rfc1123`, `iso8601` or a Day.js style format such as `"DD-MM-YYYY"`.
+fn format_datetime<Tz: TimeZone>(time: DateTime<Tz>, format: &str) -> Option<String>
+where
+    Tz::Offset: fmt::Display,
+{
+    let utc = time.offset().fix().local_minus_utc() == 0;
+    match format {
+        "rfc1123" if utc => Some(time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+        "rfc1123" => Some(time.to_rfc2822()),
+        "iso8601" => Some(time.to_rfc3339_opts(SecondsFormat::Millis, utc)),
+        custom => Some(time.format(&strftime(custom)).to_string()),
+    }
+}
+
+/// Day.js format tokens, longest first, and their strftime equivalents.
+const TOKENS: [(&str, &str); 24] = [
+    ("YYYY", "%Y"),
+    ("YY", "%y"),
+    ("MMMM", "%B"),
+    ("MMM", "%b"),
+    ("MM", "%m"),
+    ("M", "%-m"),
+    ("DD", "%d"),
+    ("D", "%-d"),
+    ("dddd", "%A"),
+    ("ddd", "%a"),
+    ("HH", "%H"),
+    ("H", "%-H"),
+    ("hh", "%I"),
+    ("h", "%-I"),
+    ("mm", "%M"),
+    ("m", "%-M"),
+    ("ss", "%S"),
+    ("s", "%-S"),
+    ("SSS", "%3f"),
+    ("A", "%p"),
+    ("a", "%P"),
+    ("ZZ", "%z"),
+    ("Z", "%:z"),
+    ("X", "%s"),
+];
+
+/// Converts a Day.js format, text in `[brackets]` is kept literally.
+fn strftime(format: &str) -> String {
+    let mut out = String::with_capacity(format.len() * 2);
+    let mut rest = format;
+    'outer: while let Some(c) = rest.chars().next() {
+        if c == '[' {
+            if let Some(end) = rest.find(']') {
+                out.push_str(&rest[1..end].replace('%', "%%"));
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+        for (token, spec) in TOKENS {
+            if let Some(after) = rest.strip_prefix(token) {
+                out.push_str(spec);
+                rest = after;
+                continue 'outer;
+            }
+        }
+        if c == '%' {
+            out.push('%');
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// Splits on whitespace, single or double quotes group words. `None` on an unclosed quote.
+fn split_args(text: &str) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let (arg, after) = match c {
+            '"' | '\'' => {
+                let end = rest[1..].find(c)? + 1;
+                (&rest[1..end], &rest[end + 1..])
+            }
+            _ => {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                rest.split_at(end)
+            }
+        };
+        args.push(arg.to_string());
+        rest = after.trim_start();
+    }
+
+    Some(args)
+}
+
+/// Parses `NAME=value` lines, skipping comments and `export` prefixes.
+fn parse_dotenv(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (name, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = ['"', '\'']
+                .iter()
+                .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+                .unwrap_or(value);
+            Some((name.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dynamic() -> DynamicVariables {
+        DynamicVariables {
+            now: Utc.with_ymd_and_hms(2022, 6, 1, 12, 30, 5).unwrap(),
+            dotenv: parse_dotenv("# secrets\nTOKEN=abc\nexport QUOTED = \"a b\"\n"),
+        }
+    }
+
+    #[test]
+    fn resolve_datetime() {
+        let vars = dynamic();
+
+        assert_eq!(vars.resolve("$timestamp").as_deref(), Some("1654086605"));
+        assert_eq!(
+            vars.resolve("$timestamp -1 d").as_deref(),
+            Some("1654000205")
+        );
+        assert_eq!(
+            vars.resolve("$datetime iso8601").as_deref(),
+            Some("2022-06-01T12:30:05.000Z")
+        );
+        assert_eq!(
+            vars.resolve("$datetime rfc1123 1 y").as_deref(),
+            Some("Thu, 01 Jun 2023 12:30:05 GMT")
+        );
+        assert_eq!(
+            vars.resolve("$datetime \"DD-MM-YYYY [at] HH:mm\" -2 M")
+                .as_deref(),
+            Some("01-04-2022 at 12:30")
+        );
+        assert_eq!(vars.resolve("$datetime iso8601 1 fortnight"), None);
+    }
+
+    #[test]
+    fn resolve_random_and_env() {
+        let vars = dynamic();
+
+        let n: i64 = vars.resolve("$randomInt 5 8").unwrap().parse().unwrap();
+        let uuid = vars.resolve("$uuid").unwrap();
+
+        assert!((5..8).contains(&n));
+        assert_eq!(vars.resolve("$randomInt 8 5"), None);
+        assert_eq!(uuid.len(), 36);
+        assert_ne!(vars.resolve("$uuid"), Some(uuid));
+        assert_eq!(vars.resolve("$dotenv TOKEN").as_deref(), Some("abc"));
+        assert_eq!(vars.resolve("$dotenv QUOTED").as_deref(), Some("a b"));
+        assert_eq!(vars.resolve("$dotenv MISSING"), None);
+        assert_eq!(vars.resolve("$processEnv PATH"), env::var("PATH").ok());
+        assert_eq!(vars.resolve("uuid"), None);
+    }
+}
@@ -0,0 +1,190 @@
+mod dynamic;
+
+use std::collections::HashMap;
+
+pub use dynamic::{DynamicVariables, DOTENV_FILE};
+
+use crate::{Error, Result};
+
+/// How deep variables may reference other variables before giving up.
+const MAX_DEPTH: usize = 16;
+
+/// File-level variable declared as `@name = value`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Variable {
+    pub name: String,
+    pub value: String,
+    pub line: usize,
+}
+
+impl Variable {
+    pub(crate) fn parse(line: &str, lineno: usize) -> Option<Variable> {
+        let (name, value) = line.strip_prefix('@')?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return None;
+        }
+
+        Some(Variable {
+            name: name.to_string(),
+            value: value.trim().to_string(),
+            line: lineno,
+        })
+    }
+}
+
+/// Source of values for `{{name}}` placeholders.
+pub trait Resolver {
+    fn resolve(&self, name: &str) -> Option<String>;
+}
+
+impl<R: Resolver + ?Sized> Resolver for &R {
+    fn resolve(&self, name: &str) -> Option<String> {
+        (**self).resolve(name)
+    }
+}
+
+/// Later declarations shadow earlier ones.
+impl Resolver for [Variable] {
+    fn resolve(&self, name: &str) -> Option<String> {
+        self.iter()
+            .rev()
+            .find(|v| v.name == name)
+            .map(|v| v.value.clone())
+    }
+}
+
+impl Resolver for HashMap<String, String> {
+    fn resolve(&self, name: &str) -> Option<String> {
+        self.get(name).cloned()
+    }
+}
+
+impl<R: Resolver> Resolver for Option<R> {
+    fn resolve(&self, name: &str) -> Option<String> {
+        self.as_ref()?.resolve(name)
+    }
+}
+
+/// Tries the first resolver, then falls back to the second.
+impl<A: Resolver, B: Resolver> Resolver for (A, B) {
+    fn resolve(&self, name: &str) -> Option<String> {
+        self.0.resolve(name).or_else(|| self.1.resolve(name))
+    }
+}
+
+/// Replaces every `{{name}}` in `text`, which starts at line `line`.
+pub fn substitute<R: Resolver + ?Sized>(text: &str, line: usize, resolver: &R) -> Result<String> {
+    substitute_depth(text, line, resolver, 0)
+}
+
+fn substitute_depth<R: Resolver + ?Sized>(
+    text: &str,
+    line: usize,
+    resolver: &R,
+    depth: usize,
+) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut line = line;
+
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start..].find("}}") {
+            Some(end) => start + end,
+            None => break,
+        };
+        let (before, after) = rest.split_at(start);
+        line += before.matches('\n').count();
+        out.push_str(before);
+
+        let name = after[2..end - start].trim();
+        let value = resolver
+            .resolve(name)
+            .ok_or_else(|| Error::UndefinedVariable {
+                name: name.to_string(),
+                line,
+            })?;
+        if value.contains("{{") {
+            if depth >= MAX_DEPTH {
+                return Err(Error::VariableRecursion {
+                    name: name.to_string(),
+                    line,
+                });
+            }
+            out.push_str(&substitute_depth(&value, line, resolver, depth + 1)?);
+        } else {
+            out.push_str(&value);
+        }
+
+        line += after[..end - start + 2].matches('\n').count();
+        rest = &after[end - start + 2..];
+    }
+    out.push_str(rest);
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<Variable> {
+        pairs
+            .iter()
+            .map(|(name, value)| Variable {
+                name: name.to_string(),
+                value: value.to_string(),
+                line: 1,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_declaration() {
+        let var = Variable::parse("@baseUrl = https://example.com", 3);
+
+        assert_eq!(
+            var,
+            Some(Variable {
+                name: "baseUrl".to_owned(),
+                value: "https://example.com".to_owned(),
+                line: 3,
+            })
+        );
+        assert_eq!(Variable::parse("@ = x", 1), None);
+        assert_eq!(Variable::parse("@name", 1), None);
+    }
+
+    #[test]
+    fn substitute_nested() -> Result<()> {
+        let vars = vars(&[("host", "example.com"), ("baseUrl", "https://{{host}}")]);
+
+        let text = substitute("{{ baseUrl }}/users/{{id}}", 1, vars.as_slice());
+
+        assert!(matches!(
+            text,
+            Err(Error::UndefinedVariable { ref name, line: 1 }) if name == "id"
+        ));
+        let vars = [vars, self::vars(&[("id", "42")])].concat();
+        assert_eq!(
+            substitute("{{ baseUrl }}/users/{{id}}", 1, vars.as_slice())?,
+            "https://example.com/users/42"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn substitute_reports_line() {
+        let vars = vars(&[("a", "{{b}}"), ("b", "{{a}}")]);
+
+        let undefined = substitute("x\ny\n{{missing}}", 5, vars.as_slice());
+        let recursive = substitute("{{a}}", 1, vars.as_slice());
+
+        assert!(matches!(
+            undefined,
+            Err(Error::UndefinedVariable { line: 7, .. })
+        ));
+        assert!(matches!(recursive, Err(Error::VariableRecursion { .. })));
+    }
+}
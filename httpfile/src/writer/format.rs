@@ -0,0 +1,326 @@
+use std::io;
+use std::str::FromStr;
+
+use crate::parser::is_json;
+use crate::{parse, parse_syntax, Error, Line, LineKind, RequestSyntax, Result, Variable};
+
+/// How [`format_file`] writes header names, names with placeholders are kept as written.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum HeaderCase {
+    #[default]
+    Preserve,
+    /// `content-type`, as HTTP/2 sends them.
+    Lower,
+    /// `Content-Type`.
+    Title,
+}
+
+impl HeaderCase {
+    fn apply(&self, name: &str) -> String {
+        if name.contains("{{") {
+            return name.to_string();
+        }
+        match self {
+            HeaderCase::Preserve => name.to_string(),
+            HeaderCase::Lower => name.to_ascii_lowercase(),
+            HeaderCase::Title => name
+                .split('-')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => {
+                            first.to_ascii_uppercase().to_string()
+                                + &chars.as_str().to_ascii_lowercase()
+                        }
+                        None => String::new(),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("-"),
+        }
+    }
+}
+
+impl FromStr for HeaderCase {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<HeaderCase> {
+        match text.to_ascii_lowercase().as_str() {
+            "preserve" => Ok(HeaderCase::Preserve),
+            "lower" => Ok(HeaderCase::Lower),
+            "title" => Ok(HeaderCase::Title),
+            _ => Err(Error::UnknownHeaderCase(text.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FormatOptions {
+    pub header_case: HeaderCase,
+    /// Spaces per level of pretty-printed JSON bodies.
+    pub json_indent: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            header_case: HeaderCase::default(),
+            json_indent: 2,
+        }
+    }
+}
+
+/// Rewrites a .http file in a consistent layout, keeping comments and what every request sends.
+pub fn format_file(source: &str, options: &FormatOptions) -> Result<String> {
+    parse(io::Cursor::new(source))?;
+    let tree = parse_syntax(source);
+
+    let mut lines = Vec::new();
+    for block in &tree.blocks {
+        let leading = leading(&block.leading, block.request.is_some());
+        if leading.is_empty() && block.request.is_none() {
+            continue;
+        }
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        if let Some(separator) = &block.separator {
+            let title = separator.content().trim().trim_start_matches('#').trim();
+            match title {
+                "" => lines.push("###".to_string()),
+                title => lines.push(format!("### {}", title)),
+            }
+        }
+        lines.extend(leading);
+        if let Some(req) = &block.request {
+            request(&mut lines, req, options);
+        }
+    }
+
+    let newline = match source.find('\n') {
+        Some(i) if source[..i].ends_with('\r') => "\r\n",
+        _ => "\n",
+    };
+    let mut out = lines.join(newline);
+    if !out.is_empty() {
+        out.push_str(newline);
+    }
+    Ok(out)
+}
+
+/// Comments and variables before a request line, with single blank lines between groups.
+fn leading(lines: &[Line], request: bool) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for line in lines {
+        let text = line.content().trim();
+        match line.kind {
+            LineKind::Blank if out.last().is_none_or(String::is_empty) => {}
+            LineKind::Variable => match Variable::parse(text, line.span.line) {
+                Some(var) => out.push(format!("@{} = {}", var.name, var.value)),
+                None => out.push(text.to_string()),
+            },
+            _ => out.push(text.to_string()),
+        }
+    }
+    if !request && out.last().is_some_and(String::is_empty) {
+        out.pop();
+    }
+    out
+}
+
+fn request(out: &mut Vec<String>, req: &RequestSyntax, options: &FormatOptions) {
+    let request_line = req.request_line.content().split_whitespace();
+    out.push(request_line.collect::<Vec<_>>().join(" "));
+
+    for line in &req.headers {
+        let text = line.content().trim();
+        match line.kind {
+            LineKind::Query | LineKind::Folded => out.push(format!("    {}", text)),
+            LineKind::Header => {
+                let (name, value) = text.split_once(':').unwrap_or((text, ""));
+                let name = options.header_case.apply(name);
+                match value.trim() {
+                    "" => out.push(format!("{}:", name)),
+                    value => out.push(format!("{}: {}", name, value)),
+                }
+            }
+            _ => out.push(text.to_string()),
+        }
+    }
+
+    // Whitespace after the body isn't sent, whitespace before it is.
+    let body: Vec<&str> = req.body.iter().map(Line::content).collect();
+    let body = body.join("\n");
+    let body = body.trim_end();
+    if body.is_empty() {
+        return;
+    }
+    out.push(String::new());
+    let json = req
+        .header("content-type")
+        .is_some_and(is_json)
+        .then(|| pretty_json(body, options.json_indent))
+        .flatten();
+    match json {
+        Some(json) => out.extend(json.lines().map(str::to_string)),
+        None => out.extend(body.split('\n').map(str::to_string)),
+    }
+}
+
+/// Re-indents `text` if it's valid JSON, copying tokens so that numbers keep their spelling.
+fn pretty_json(text: &str, indent: usize) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(text).ok()?;
+
+    let mut out = String::new();
+    let mut depth = 0;
+    let newline = |out: &mut String, depth: usize| {
+        out.push('\n');
+        out.push_str(&" ".repeat(depth * indent));
+    };
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                out.push(c);
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    match c {
+                        '\\' => out.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '{' | '[' => {
+                out.push(c);
+                while chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+                match chars.next_if(|c| matches!(c, '}' | ']')) {
+                    Some(end) => out.push(end),
+                    None => {
+                        depth += 1;
+                        newline(&mut out, depth);
+                    }
+                }
+            }
+            '}' | ']' => {
+                depth -= 1;
+                newline(&mut out, depth);
+                out.push(c);
+            }
+            ',' => {
+                out.push(c);
+                newline(&mut out, depth);
+            }
+            ':' => out.push_str(": "),
+            c if c.is_ascii_whitespace() => {}
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_layout() -> Result<()> {
+        let input = "
+
+@host   =  example.com
+# Users
+
+
+GET   https://{{host}}/users   HTTP/1.1
+  ?page=2
+accept:application/json
+x-trace-id :  abc
+###
+###
+
+
+POST https://{{host}}/users
+content-type: application/json; charset=utf-8
+
+{\"name\":\"sample\",\"score\":1.50,\"tags\":[],\"nested\":{\"a\":[1,2]}}
+
+
+########   Plain
+POST https://{{host}}/notes
+Content-Type: text/plain
+
+  keep   this
+{\"id\": {{id}}}
+";
+        let options = FormatOptions {
+            header_case: HeaderCase::Title,
+            json_indent: 2,
+        };
+
+        let formatted = format_file(input, &options);
+
+        assert!(matches!(formatted, Err(Error::Syntax { line: 10, .. })));
+
+        let input = input.replace("x-trace-id :", "x-trace-id:");
+        let formatted = format_file(&input, &options)?;
+
+        assert_eq!(
+            formatted,
+            "@host = example.com
+# Users
+
+GET https://{{host}}/users HTTP/1.1
+    ?page=2
+Accept: application/json
+X-Trace-Id: abc
+
+###
+POST https://{{host}}/users
+Content-Type: application/json; charset=utf-8
+
+{
+  \"name\": \"sample\",
+  \"score\": 1.50,
+  \"tags\": [],
+  \"nested\": {
+    \"a\": [
+      1,
+      2
+    ]
+  }
+}
+
+### Plain
+POST https://{{host}}/notes
+Content-Type: text/plain
+
+  keep   this
+{\"id\": {{id}}}
+"
+        );
+        assert_eq!(format_file(&formatted, &options)?, formatted);
+
+        Ok(())
+    }
+
+    #[test]
+    fn format_keeps_requests() -> Result<()> {
+        let input = "# @name first\r\nGET https://example.com/a\r\nx-a:1\r\n\r\n\r\n###\r\n\r\n// second\r\nPOST https://example.com/b\r\ncontent-type: application/json\r\n\r\n[ {\"b\" : \"x,y\\\"\"} ]\r\n";
+
+        let formatted = format_file(input, &FormatOptions::default())?;
+        let before = parse(io::Cursor::new(input))?;
+        let after = parse(io::Cursor::new(&formatted))?;
+
+        assert!(!formatted.replace("\r\n", "").contains('\n'));
+        assert!(formatted.ends_with("\r\n[\r\n  {\r\n    \"b\": \"x,y\\\"\"\r\n  }\r\n]\r\n"));
+        assert_eq!(before.requests.len(), after.requests.len());
+        for (before, after) in before.requests.iter().zip(&after.requests) {
+            assert_eq!(before.comment, after.comment);
+            assert_eq!(before.name, after.name);
+            assert_eq!(before.url, after.url);
+            assert_eq!(before.headers.len(), after.headers.len());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+mod format;
+
+use std::fmt;
+use std::io;
+
+pub use format::{format_file, FormatOptions, HeaderCase};
+
+use crate::{Collection, HttpRequest};
+
+/// Writes the request in canonical .http syntax, without a trailing line break.
+impl fmt::Display for HttpRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in self.comment.lines() {
+            writeln!(f, "{}", line)?;
+        }
+        if let Some(name) = &self.name {
+            writeln!(f, "# @name {}", name)?;
+        }
+        for (directive, value) in &self.settings {
+            match value.as_str() {
+                "" => writeln!(f, "# @{}", directive)?,
+                value => writeln!(f, "# @{} {}", directive, value)?,
+            }
+        }
+        write!(
+            f,
+            "{} {} {}",
+            self.method_text(),
+            self.url,
+            self.version_text()
+        )?;
+        for h in &self.headers {
+            write!(f, "\n{}: {}", h.name, h.value)?;
+        }
+        if !self.body.is_empty() {
+            write!(f, "\n\n{}", self.body)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes `requests` separated by `###`, so that [`parse`](crate::parse) reads them back.
+pub fn write_file<W: io::Write>(mut w: W, requests: &[HttpRequest]) -> io::Result<()> {
+    for (i, req) in requests.iter().enumerate() {
+        if i > 0 {
+            writeln!(w, "###")?;
+        }
+        writeln!(w, "{}\n", req)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the variables, then each request after a `###` line that carries the section title.
+pub fn write_collection<W: io::Write>(mut w: W, collection: &Collection) -> io::Result<()> {
+    for var in &collection.variables {
+        writeln!(w, "@{} = {}", var.name, var.value)?;
+    }
+    if !collection.variables.is_empty() {
+        writeln!(w)?;
+    }
+    for section in &collection.sections {
+        for (i, req) in section.requests.iter().enumerate() {
+            match &section.title {
+                Some(title) if i == 0 => writeln!(w, "### {}", title)?,
+                _ => writeln!(w, "###")?,
+            }
+            writeln!(w, "{}\n", req)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{parse, Body, Directive, Header, HttpVersion, Multipart, Part, Span, Spelling};
+
+    fn header() -> impl Strategy<Value = Header> {
+        ("[A-Za-z][A-Za-z0-9-]{0,15}", "([!-~]([ -~]{0,20}[!-~])?)?").prop_map(|(name, value)| {
+            Header {
+                name,
+                value,
+                span: Span::default(),
+            }
+        })
+    }
+
+    /// Lines of text that can't be mistaken for a separator or a file reference.
+    fn text() -> impl Strategy<Value = String> {
+        prop::collection::vec("[A-Za-z0-9{}\":,]([ -~]{0,30}[!-~])?", 1..5)
+            .prop_map(|lines| lines.join("\n"))
+    }
+
+    fn body() -> impl Strategy<Value = Body> {
+        prop_oneof![
+            Just(Body::default()),
+            text().prop_map(Body::Text),
+            ("[a-z./]{1,20}", any::<bool>()).prop_map(|(path, substitute)| Body::File {
+                path: path.into(),
+                substitute,
+            }),
+        ]
+    }
+
+    fn multipart() -> impl Strategy<Value = Body> {
+        let part = ("[a-z]{1,10}", body()).prop_map(|(name, body)| Part {
+            headers: vec![Header {
+                name: "Content-Disposition".to_owned(),
+                value: format!("form-data; name=\"{}\"", name),
+                span: Span::default(),
+            }],
+            body,
+        });
+        prop::collection::vec(part, 1..4).prop_map(|parts| {
+            Body::Multipart(Multipart {
+                boundary: "boundary".to_owned(),
+                parts,
+            })
+        })
+    }
+
+    fn request() -> impl Strategy<Value = HttpRequest> {
+        (
+            prop::collection::vec("(#|//)( [A-Za-z0-9]([ -~]{0,20}[!-~])?)?", 0..3),
+            prop::option::of("[a-z][a-z0-9_]{0,10}"),
+            prop::sample::subsequence(
+                vec![
+                    (Directive::NoRedirect, ""),
+                    (Directive::NoCookieJar, ""),
+                    (Directive::Timeout, "30"),
+                    (Directive::ConnectionTimeout, "500 ms"),
+                ],
+                0..=4,
+            ),
+            "[A-Z]{3,7}",
+            "https://[a-z]{1,10}\\.com(/([a-z0-9]{1,8}|\\{\\{[a-z]{1,5}\\}\\})){0,3}(\\?[a-z]{1,5}=[a-z0-9]{1,5})?",
+            prop::sample::select(vec![
+                HttpVersion::Http10,
+                HttpVersion::Http11,
+                HttpVersion::Http2,
+            ]),
+            prop::collection::vec(header(), 0..4),
+            prop_oneof![4 => body(), 1 => multipart()],
+        )
+            .prop_map(
+                |(comment, name, settings, method, url, version, mut headers, body)| {
+                if let Body::Multipart(_) = body {
+                    headers.push(Header {
+                        name: "Content-Type".to_owned(),
+                        value: "multipart/form-data; boundary=boundary".to_owned(),
+                        span: Span::default(),
+                    });
+                }
+                HttpRequest {
+                    comment: comment.join("\n"),
+                    name,
+                    settings: settings
+                        .into_iter()
+                        .map(|(directive, value)| (directive, value.to_owned()))
+                        .collect(),
+                    method: method.parse().unwrap(),
+                    url: url.parse().unwrap(),
+                    version,
+                    spelling: Spelling::default(),
+                    headers,
+                    body,
+                    span: Span::default(),
+                    body_span: Span::default(),
+                }
+            },
+            )
+    }
+
+    /// Spans depend on the layout of the source, so they are left out of comparisons.
+    fn without_spans(mut req: HttpRequest) -> HttpRequest {
+        req.span = Span::default();
+        req.body_span = Span::default();
+        for h in req.headers.iter_mut() {
+            h.span = Span::default();
+        }
+        if let Body::Multipart(multipart) = &mut req.body {
+            for h in multipart
+                .parts
+                .iter_mut()
+                .flat_map(|p| p.headers.iter_mut())
+            {
+                h.span = Span::default();
+            }
+        }
+        req
+    }
+
+    #[test]
+    fn write_canonical() -> crate::Result<()> {
+        let input = "// Create a user
+POST https://example.com/users HTTP/1.1
+# @name create
+Content-Type:application/json
+
+{\"name\": \"sample\"}
+###
+GET https://example.com/users
+###
+delete https://example.com/users/1 HTTP/2.0
+";
+
+        let document = parse(io::Cursor::new(input))?;
+        let mut out = Vec::new();
+        write_file(&mut out, &document.requests)?;
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "// Create a user
+# @name create
+POST https://example.com/users HTTP/1.1
+Content-Type: application/json
+
+{\"name\": \"sample\"}
+
+###
+GET https://example.com/users HTTP/1.1
+
+###
+delete https://example.com/users/1 HTTP/2.0
+
+"
+        );
+
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn write_then_parse(requests in prop::collection::vec(request(), 1..4)) {
+            let mut out = Vec::new();
+            write_file(&mut out, &requests).unwrap();
+
+            let document = parse(io::Cursor::new(out)).unwrap();
+            let parsed: Vec<_> = document.requests.into_iter().map(without_spans).collect();
+
+            prop_assert_eq!(parsed, requests);
+        }
+    }
+}
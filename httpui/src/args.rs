@@ -1,13 +1,200 @@
 use std::path::PathBuf;
 
 use argh::FromArgs;
+use httpfile::{HeaderCase, Language, Rule};
 
 #[derive(FromArgs)]
 /// tui for .http files
 pub struct Args {
+    /// path to a .http file
+    #[argh(positional)]
+    pub path: Option<PathBuf>,
+
+    /// name of the environment from http-client.env.json
+    #[argh(option)]
+    pub env: Option<String>,
+
+    /// file `h` saves the session to as HAR, defaults to the .http path with a .har extension
+    #[argh(option)]
+    pub har: Option<PathBuf>,
+
+    #[argh(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+pub enum Command {
+    ImportCurl(ImportCurl),
+    ExportCurl(ExportCurl),
+    Import(Import),
+    Codegen(Codegen),
+    Fmt(Fmt),
+    Lint(Lint),
+}
+
+#[derive(FromArgs)]
+/// print a curl command as a .http request
+#[argh(subcommand, name = "import-curl")]
+pub struct ImportCurl {
+    /// the whole curl command as one argument, read from stdin when omitted
+    #[argh(positional)]
+    pub command: Option<String>,
+}
+
+#[derive(FromArgs)]
+/// print requests of a .http file as curl commands
+#[argh(subcommand, name = "export-curl")]
+pub struct ExportCurl {
+    /// path to a .http file
+    #[argh(positional)]
+    pub path: PathBuf,
+
+    /// only export the request called `# @name <name>`
+    #[argh(option)]
+    pub name: Option<String>,
+
+    /// name of the environment from http-client.env.json
+    #[argh(option)]
+    pub env: Option<String>,
+}
+
+#[derive(FromArgs)]
+/// print requests of a .http file as code
+#[argh(subcommand, name = "codegen")]
+pub struct Codegen {
     /// path to a .http file
     #[argh(positional)]
     pub path: PathBuf,
+
+    /// rust, python, javascript or go
+    #[argh(option)]
+    pub lang: Language,
+
+    /// only generate the request called `# @name <name>`
+    #[argh(option)]
+    pub name: Option<String>,
+
+    /// name of the environment from http-client.env.json
+    #[argh(option)]
+    pub env: Option<String>,
+}
+
+#[derive(FromArgs)]
+/// rewrite .http files in a consistent layout
+#[argh(subcommand, name = "fmt")]
+pub struct Fmt {
+    /// paths to .http files
+    #[argh(positional)]
+    pub paths: Vec<PathBuf>,
+
+    /// list files that aren't formatted and exit with an error instead of rewriting them
+    #[argh(switch)]
+    pub check: bool,
+
+    /// preserve, lower or title, how header names are written
+    #[argh(option, default = "HeaderCase::Preserve")]
+    pub header_case: HeaderCase,
+
+    /// spaces per level of JSON bodies, defaults to 2
+    #[argh(option, default = "2")]
+    pub indent: usize,
+}
+
+#[derive(FromArgs)]
+/// check .http files for mistakes, exits with an error when a rule at error level fails
+#[argh(subcommand, name = "lint")]
+pub struct Lint {
+    /// paths to .http files
+    #[argh(positional)]
+    pub paths: Vec<PathBuf>,
+
+    /// name of the environment from http-client.env.json, variables of any environment
+    /// count as defined when omitted
+    #[argh(option)]
+    pub env: Option<String>,
+
+    /// report a rule as an error, can be repeated
+    #[argh(option)]
+    pub deny: Vec<Rule>,
+
+    /// report a rule as a warning, can be repeated
+    #[argh(option)]
+    pub warn: Vec<Rule>,
+
+    /// turn a rule off, can be repeated
+    #[argh(option)]
+    pub allow: Vec<Rule>,
+}
+
+#[derive(FromArgs)]
+/// convert a collection of another tool to a .http file
+#[argh(subcommand, name = "import")]
+pub struct Import {
+    #[argh(subcommand)]
+    pub format: ImportFormat,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+pub enum ImportFormat {
+    Postman(ImportPostman),
+    Har(ImportHar),
+    Openapi(ImportOpenapi),
+    Insomnia(ImportInsomnia),
+}
+
+#[derive(FromArgs)]
+/// convert a Postman v2.1 collection
+#[argh(subcommand, name = "postman")]
+pub struct ImportPostman {
+    /// path to the exported collection
+    #[argh(positional)]
+    pub file: PathBuf,
+
+    /// path of the .http file to write, defaults to the collection path with a .http extension
+    #[argh(option, short = 'o')]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(FromArgs)]
+/// convert the requests of a HAR 1.2 capture
+#[argh(subcommand, name = "har")]
+pub struct ImportHar {
+    /// path to the .har file
+    #[argh(positional)]
+    pub file: PathBuf,
+
+    /// path of the .http file to write, defaults to the capture path with a .http extension
+    #[argh(option, short = 'o')]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(FromArgs)]
+/// generate a request for each operation of an OpenAPI 3 document, relative server URLs are put on
+/// localhost and @baseUrl has to be set to the real host
+#[argh(subcommand, name = "openapi")]
+pub struct ImportOpenapi {
+    /// path to the JSON or YAML document
+    #[argh(positional)]
+    pub file: PathBuf,
+
+    /// path of the .http file to write, defaults to the document path with a .http extension
+    #[argh(option, short = 'o')]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(FromArgs)]
+/// convert an Insomnia v4 export, environments go to http-client.env.json next to the output
+#[argh(subcommand, name = "insomnia")]
+pub struct ImportInsomnia {
+    /// path to the exported JSON file
+    #[argh(positional)]
+    pub file: PathBuf,
+
+    /// path of the .http file to write, defaults to the export path with a .http extension
+    #[argh(option, short = 'o')]
+    pub output: Option<PathBuf>,
 }
 
 pub fn parse() -> Args {
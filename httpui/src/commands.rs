@@ -0,0 +1,283 @@
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use httpfile::{
+    Document, DynamicVariables, Environments, FormatOptions, HttpRequest, LintConfig, Resolver,
+    Severity,
+};
+use httpui::report;
+
+use crate::args::{Codegen, Command, ExportCurl, Fmt, Import, ImportCurl, ImportFormat, Lint};
+
+pub fn run(command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::ImportCurl(args) => import_curl(args),
+        Command::ExportCurl(args) => export_curl(args),
+        Command::Import(args) => import(args),
+        Command::Codegen(args) => codegen(args),
+        Command::Fmt(args) => fmt(args),
+        Command::Lint(args) => lint(args),
+    }
+}
+
+/// Parses the .http file at `path`, reports syntax errors and exits on failure.
+fn load(path: &Path) -> Result<Document, Box<dyn Error>> {
+    let source = fs::read_to_string(path)?;
+    let mut document = match httpfile::parse(io::Cursor::new(&source)) {
+        Ok(document) => document,
+        Err(err) => {
+            eprint!("{}", report::render(path, &source, &err));
+            process::exit(1);
+        }
+    };
+    document.rebase(path.parent().unwrap_or_else(|| Path::new("")));
+    Ok(document)
+}
+
+fn import_curl(args: ImportCurl) -> Result<(), Box<dyn Error>> {
+    let command = match args.command {
+        Some(command) => command,
+        None => {
+            let mut command = String::new();
+            io::stdin().read_to_string(&mut command)?;
+            command
+        }
+    };
+    let req = httpfile::parse_curl(&command)?;
+    httpfile::write_file(io::stdout().lock(), &[req])?;
+    Ok(())
+}
+
+fn export_curl(args: ExportCurl) -> Result<(), Box<dyn Error>> {
+    let requests = resolve(&args.path, args.name.as_deref(), args.env.as_deref())?;
+    let commands: Vec<_> = requests.iter().map(httpfile::to_curl).collect();
+    println!("{}", commands.join("\n\n"));
+
+    Ok(())
+}
+
+fn codegen(args: Codegen) -> Result<(), Box<dyn Error>> {
+    let requests = resolve(&args.path, args.name.as_deref(), args.env.as_deref())?;
+    let snippets: Vec<_> = requests
+        .iter()
+        .map(|req| httpfile::to_code(req, args.lang))
+        .collect();
+    println!("{}", snippets.join("\n\n"));
+
+    Ok(())
+}
+
+/// Rewrites the files that change, with `--check` only lists them and exits with 1.
+fn fmt(args: Fmt) -> Result<(), Box<dyn Error>> {
+    if args.paths.is_empty() {
+        return Err("missing path to a .http file".into());
+    }
+    let options = FormatOptions {
+        header_case: args.header_case,
+        json_indent: args.indent,
+    };
+
+    let mut unformatted = false;
+    for path in &args.paths {
+        let source = fs::read_to_string(path)
+            .map_err(|err| format!("couldn't read {}: {}", path.display(), err))?;
+        let formatted = match httpfile::format_file(&source, &options) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                eprint!("{}", report::render(path, &source, &err));
+                process::exit(1);
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if args.check {
+            println!("{}", path.display());
+            unformatted = true;
+        } else {
+            fs::write(path, formatted)?;
+        }
+    }
+
+    if unformatted {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Prints the lints of every file, exits with 1 when one of them is an error.
+fn lint(args: Lint) -> Result<(), Box<dyn Error>> {
+    if args.paths.is_empty() {
+        return Err("missing path to a .http file".into());
+    }
+    let mut config = LintConfig::default();
+    for (rules, severity) in [
+        (&args.deny, Some(Severity::Error)),
+        (&args.warn, Some(Severity::Warning)),
+        (&args.allow, None),
+    ] {
+        for rule in rules {
+            config.set(*rule, severity);
+        }
+    }
+
+    let mut failed = false;
+    for path in &args.paths {
+        let source = fs::read_to_string(path)
+            .map_err(|err| format!("couldn't read {}: {}", path.display(), err))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let environments = Environments::load(dir)?;
+        let environment = match &args.env {
+            Some(env) => Some(
+                environments
+                    .get(env)
+                    .ok_or_else(|| format!("environment `{}` is not defined", env))?,
+            ),
+            None => None,
+        };
+        let environment: &dyn Resolver = match &environment {
+            Some(environment) => environment,
+            None => &environments,
+        };
+        let variables = (environment, DynamicVariables::load(dir)?);
+
+        let lints = match httpfile::lint(&source, &variables, &config) {
+            Ok(lints) => lints,
+            Err(err) => {
+                eprint!("{}", report::render(path, &source, &err));
+                failed = true;
+                continue;
+            }
+        };
+        for lint in &lints {
+            eprint!("{}", report::render_lint(path, &source, lint));
+            failed |= lint.severity == Severity::Error;
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Requests of the .http file at `path`, or only the one called `name`, resolved.
+fn resolve(
+    path: &Path,
+    name: Option<&str>,
+    env: Option<&str>,
+) -> Result<Vec<HttpRequest>, Box<dyn Error>> {
+    let document = load(path)?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let environment = match env {
+        Some(env) => Some(
+            Environments::load(dir)?
+                .get(env)
+                .ok_or_else(|| format!("environment `{}` is not defined", env))?,
+        ),
+        None => None,
+    };
+    let variables = (
+        document.variables.as_slice(),
+        (environment, DynamicVariables::load(dir)?),
+    );
+
+    let requests: Vec<_> = document
+        .requests
+        .iter()
+        .filter(|req| name.is_none() || req.name.as_deref() == name)
+        .collect();
+    if let (Some(name), true) = (name, requests.is_empty()) {
+        return Err(format!("no request is called `{}`", name).into());
+    }
+    let resolved = requests
+        .into_iter()
+        .map(|req| req.resolve(&variables))
+        .collect::<Result<_, _>>()?;
+
+    Ok(resolved)
+}
+
+fn import(args: Import) -> Result<(), Box<dyn Error>> {
+    match args.format {
+        ImportFormat::Postman(args) => {
+            let collection = httpfile::import_postman(&fs::read_to_string(&args.file)?)?;
+            write_output(&args.file, args.output, |file| {
+                httpfile::write_collection(file, &collection)
+            })
+        }
+        ImportFormat::Har(args) => {
+            let requests = httpfile::import_har(&fs::read_to_string(&args.file)?)?;
+            write_output(&args.file, args.output, |file| {
+                httpfile::write_file(file, &requests)
+            })
+        }
+        ImportFormat::Insomnia(args) => {
+            let (collection, environments) =
+                httpfile::import_insomnia(&fs::read_to_string(&args.file)?)?;
+            let output = args
+                .output
+                .clone()
+                .unwrap_or_else(|| args.file.with_extension("http"));
+            // Both files are created before writing either so that a clash leaves nothing behind.
+            let env_file = if environments.is_empty() {
+                None
+            } else {
+                let dir = output.parent().unwrap_or_else(|| Path::new(""));
+                let path = dir.join(httpfile::ENV_FILE);
+                Some((create_new(&path)?, path))
+            };
+            let mut file = match open_output(&args.file, args.output) {
+                Ok(file) => file,
+                Err(err) => {
+                    if let Some((_, path)) = env_file {
+                        fs::remove_file(path)?;
+                    }
+                    return Err(err);
+                }
+            };
+            httpfile::write_collection(&mut file, &collection)?;
+            if let Some((mut env_file, _)) = env_file {
+                env_file.write_all(environments.to_json().as_bytes())?;
+            }
+            Ok(())
+        }
+        ImportFormat::Openapi(args) => {
+            let collection = httpfile::import_openapi(&fs::read_to_string(&args.file)?)?;
+            write_output(&args.file, args.output, |file| {
+                httpfile::write_collection(file, &collection)
+            })
+        }
+    }
+}
+
+/// Writes to `output`, or next to `input` with a `.http` extension without overwriting.
+fn write_output(
+    input: &Path,
+    output: Option<PathBuf>,
+    write: impl FnOnce(&mut fs::File) -> io::Result<()>,
+) -> Result<(), Box<dyn Error>> {
+    write(&mut open_output(input, output)?)?;
+    Ok(())
+}
+
+/// Opens `output`, or creates the `.http` file next to `input` if it doesn't exist yet.
+fn open_output(input: &Path, output: Option<PathBuf>) -> Result<fs::File, Box<dyn Error>> {
+    match output {
+        Some(path) => Ok(fs::File::create(path)?),
+        None => create_new(&input.with_extension("http")),
+    }
+}
+
+/// Creates `path`, failing if it already exists.
+fn create_new(path: &Path) -> Result<fs::File, Box<dyn Error>> {
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(|err| format!("couldn't create {}: {}", path.display(), err))?;
+    Ok(file)
+}
@@ -1,2 +1,3 @@
 pub mod mvc;
+pub mod report;
 pub mod widgets;
@@ -1,15 +1,25 @@
-use std::time::Duration;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::{anyhow, Result};
 use crossterm::event::{Event, KeyCode, KeyEvent};
 use futures::{stream::StreamExt, FutureExt};
-use httpfile::HttpRequest;
+use httpfile::{
+    Directive, Document, DynamicVariables, Environments, Exchange, HttpRequest, HttpVersion,
+    Response, Timings,
+};
+use reqwest::cookie::Jar;
+use reqwest::redirect::Policy;
 use tokio::time;
 use tokio::{select, sync::mpsc};
 use tui::backend::Backend;
 use tui::Terminal;
 
-use super::model::Model;
+use super::model::{Entry, Model};
 use super::view::View;
 use super::AppState;
 
@@ -18,23 +28,119 @@ enum AppAction {
     Continue,
 }
 
+#[derive(Debug)]
+struct IoMessage {
+    /// Name of the request the response belongs to.
+    name: Option<String>,
+    /// Resolved request as sent, recorded with the response for the HAR export.
+    request: HttpRequest,
+    started: SystemTime,
+    resp: std::result::Result<Response, String>,
+    timings: Timings,
+}
+
 pub struct Controller {
     model: Model,
     view: View,
 
-    client: reqwest::Client,
-    channel: (mpsc::Sender<String>, mpsc::Receiver<String>),
+    clients: Clients,
+    channel: (mpsc::Sender<IoMessage>, mpsc::Receiver<IoMessage>),
+}
+
+/// Clients built on first use for each [`ClientKey`], sharing one cookie jar.
+struct Clients {
+    jar: Arc<Jar>,
+    clients: HashMap<ClientKey, reqwest::Client>,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+struct ClientKey {
+    protocol: Protocol,
+    redirect: bool,
+    cookies: bool,
+    connect_timeout: Option<Duration>,
+}
+
+impl ClientKey {
+    fn of(req: &HttpRequest) -> ClientKey {
+        let cleartext = req
+            .url
+            .scheme()
+            .is_some_and(|scheme| scheme.eq_ignore_ascii_case("http"));
+        let protocol = match req.version {
+            HttpVersion::Http2 if cleartext => Protocol::H2c,
+            HttpVersion::Http2 => Protocol::Http2,
+            _ => Protocol::Http1,
+        };
+        ClientKey {
+            protocol,
+            redirect: !req.has(Directive::NoRedirect),
+            cookies: !req.has(Directive::NoCookieJar),
+            connect_timeout: req.connection_timeout(),
+        }
+    }
+}
+
+/// How the version from the request line is spoken.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+enum Protocol {
+    Http1,
+    /// Negotiates HTTP/2 over TLS with ALPN.
+    Http2,
+    /// Speaks HTTP/2 over cleartext connections with prior knowledge.
+    H2c,
+}
+
+impl Clients {
+    fn new() -> Clients {
+        Clients {
+            jar: Arc::new(Jar::default()),
+            clients: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, req: &HttpRequest) -> reqwest::Result<reqwest::Client> {
+        let key = ClientKey::of(req);
+        if let Some(client) = self.clients.get(&key) {
+            return Ok(client.clone());
+        }
+
+        let mut builder = match key.protocol {
+            Protocol::Http1 => reqwest::Client::builder().http1_only(),
+            Protocol::Http2 => reqwest::Client::builder(),
+            Protocol::H2c => reqwest::Client::builder().http2_prior_knowledge(),
+        };
+        if !key.redirect {
+            builder = builder.redirect(Policy::none());
+        }
+        if key.cookies {
+            builder = builder.cookie_provider(self.jar.clone());
+        }
+        if let Some(timeout) = key.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        let client = builder.build()?;
+        self.clients.insert(key, client.clone());
+        Ok(client)
+    }
 }
 
 impl Controller {
-    pub fn new(items: Vec<HttpRequest>) -> Controller {
-        Controller {
-            model: Model::new(items),
+    pub fn new(
+        document: Document,
+        source: String,
+        path: PathBuf,
+        environments: Environments,
+        env: Option<String>,
+        har_path: PathBuf,
+    ) -> Result<Controller> {
+        Ok(Controller {
+            model: Model::new(document, source, path, environments, env, har_path),
             view: View::new(),
 
-            client: reqwest::Client::new(),
+            clients: Clients::new(),
             channel: mpsc::channel(32),
-        }
+        })
     }
 
     pub async fn run<B: Backend>(
@@ -80,11 +186,11 @@ impl Controller {
 }
 
 impl Controller {
-    fn io_sender(&self) -> mpsc::Sender<String> {
+    fn io_sender(&self) -> mpsc::Sender<IoMessage> {
         self.channel.0.clone()
     }
 
-    async fn receive_io(&mut self) -> Option<String> {
+    async fn receive_io(&mut self) -> Option<IoMessage> {
         self.channel.1.recv().await
     }
 
@@ -96,6 +202,25 @@ impl Controller {
     }
 
     async fn handle_keyboard_event(&mut self, key: KeyEvent) -> Result<AppAction> {
+        if let AppState::SelectingEnv = self.model.state {
+            self.handle_env_popup_key(key);
+            return Ok(AppAction::Continue);
+        }
+        if let AppState::ImportingCurl = self.model.state {
+            self.handle_curl_popup_key(key);
+            return Ok(AppAction::Continue);
+        }
+        if let AppState::SelectingLanguage = self.model.state {
+            self.handle_language_popup_key(key);
+            return Ok(AppAction::Continue);
+        }
+        if let AppState::ExportingCurl | AppState::ExportingHar | AppState::ShowingCode =
+            self.model.state
+        {
+            self.model.state = AppState::ShowingList;
+            return Ok(AppAction::Continue);
+        }
+
         match key.code {
             KeyCode::Char('q') => return Ok(AppAction::Exit),
             KeyCode::Enter => {
@@ -122,6 +247,19 @@ impl Controller {
             self.model.scroll.scroll(offset.0, offset.1);
         } else {
             match key.code {
+                KeyCode::Char('e') if !self.model.env_list.items.is_empty() => {
+                    self.model.state = AppState::SelectingEnv
+                }
+                KeyCode::Char('c') => self.handle_export_curl(),
+                KeyCode::Char('h') => self.handle_export_har(),
+                KeyCode::Char('g') if self.model.items.state.selected().is_some() => {
+                    self.model.state = AppState::SelectingLanguage
+                }
+                KeyCode::Char('i') => {
+                    self.model.curl_input.clear();
+                    self.model.curl_error = None;
+                    self.model.state = AppState::ImportingCurl;
+                }
                 KeyCode::Left => self.model.items.unselect(),
                 KeyCode::Down => self.model.items.next(),
                 KeyCode::Up => self.model.items.previous(),
@@ -132,16 +270,87 @@ impl Controller {
         Ok(AppAction::Continue)
     }
 
+    fn handle_env_popup_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Down => self.model.env_list.next(),
+            KeyCode::Up => self.model.env_list.previous(),
+            KeyCode::Enter => {
+                let name = self
+                    .model
+                    .env_list
+                    .state
+                    .selected()
+                    .and_then(|i| self.model.env_list.items.get(i));
+                self.model.environment = name.and_then(|name| self.model.environments.get(name));
+                self.model.state = AppState::ShowingList;
+            }
+            KeyCode::Backspace => {
+                self.model.environment = None;
+                self.model.state = AppState::ShowingList;
+            }
+            KeyCode::Esc => self.model.state = AppState::ShowingList,
+            _ => {}
+        }
+    }
+
+    fn handle_language_popup_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Down => self.model.lang_list.next(),
+            KeyCode::Up => self.model.lang_list.previous(),
+            KeyCode::Enter => {
+                let lang = self
+                    .model
+                    .lang_list
+                    .state
+                    .selected()
+                    .and_then(|i| self.model.lang_list.items.get(i).copied());
+                self.model.code = match (self.model.selected(), lang) {
+                    (Some(Entry::Request(req)), Some(lang)) => Some(
+                        self.resolve(req)
+                            .map(|req| httpfile::to_code(&req, lang))
+                            .map_err(|err| err.to_string()),
+                    ),
+                    (Some(Entry::Broken(diagnostic)), Some(_)) => {
+                        Some(Err(diagnostic.message.clone()))
+                    }
+                    _ => None,
+                };
+                self.model.state = AppState::ShowingCode;
+            }
+            KeyCode::Esc => self.model.state = AppState::ShowingList,
+            _ => {}
+        }
+    }
+
+    /// Pasted line breaks arrive as Enter, so Enter after `\\` continues the command.
+    fn handle_curl_popup_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char(c) => self.model.curl_input.push(c),
+            KeyCode::Backspace => {
+                self.model.curl_input.pop();
+            }
+            KeyCode::Enter if self.model.curl_input.ends_with('\\') => {
+                self.model.curl_input.push('\n')
+            }
+            KeyCode::Enter => match httpfile::parse_curl(&self.model.curl_input) {
+                Ok(mut req) => {
+                    req.rebase(&self.model.dir);
+                    let items = &mut self.model.items;
+                    items.items.push(Entry::Request(Box::new(req)));
+                    items.state.select(Some(items.items.len() - 1));
+                    self.model.state = AppState::ShowingList;
+                }
+                Err(err) => self.model.curl_error = Some(err.to_string()),
+            },
+            KeyCode::Esc => self.model.state = AppState::ShowingList,
+            _ => {}
+        }
+    }
+
     async fn handle_enter_key(&mut self) -> Result<()> {
-        let selected = self
-            .model
-            .items
-            .state
-            .selected()
-            .and_then(|i| self.model.items.items.get(i));
-        let selected = match selected {
-            Some(req) => req.clone(),
-            None => return Ok(()),
+        let selected = match self.model.selected() {
+            Some(Entry::Request(req)) => HttpRequest::clone(req),
+            _ => return Ok(()),
         };
         self.handle_do_request(selected).await?;
         Ok(())
@@ -149,40 +358,228 @@ impl Controller {
 
     async fn handle_do_request(&mut self, req: HttpRequest) -> Result<()> {
         self.model.state = AppState::DoingRequest;
+        self.model.resp = None;
+
+        let req = match self.resolve(&req) {
+            Ok(req) => req,
+            Err(err) => {
+                self.model.request = Some(req);
+                self.model.resp = Some(Err(err.to_string()));
+                return Ok(());
+            }
+        };
         self.model.request = Some(req.clone());
 
         let io = self.io_sender();
-        let req = req.to_reqwest(&self.client)?;
-        let client = self.client.clone();
+        let client = match self.clients.get(&req) {
+            Ok(client) => client,
+            Err(err) => {
+                self.model.resp = Some(Err(err.to_string()));
+                return Ok(());
+            }
+        };
+        let sent = match req.to_reqwest(&client) {
+            Ok(sent) => sent,
+            Err(err) => {
+                self.model.resp = Some(Err(err.to_string()));
+                return Ok(());
+            }
+        };
 
         tokio::spawn(async move {
-            let resp = match client.execute(req).await {
-                Ok(resp) => resp,
-                Err(err) => {
-                    io.send(err.to_string()).await.expect("msg to be sent");
-                    return;
-                }
-            };
-
-            let body = match resp.text().await {
-                Ok(body) => body,
-                Err(err) => {
-                    io.send(err.to_string()).await.expect("msg to be sent");
-                    return;
-                }
+            let started = SystemTime::now();
+            let mut timings = Timings::default();
+            let resp = execute(client, sent, &mut timings)
+                .await
+                .map_err(|err| send_error(err, req.version));
+            let msg = IoMessage {
+                name: req.name.clone(),
+                request: req,
+                started,
+                resp,
+                timings,
             };
-
-            io.send(body).await.expect("msg to be sent");
+            io.send(msg).await.expect("msg to be sent");
         });
         Ok(())
     }
 
+    /// Substitutes file, environment, response and dynamic variables.
+    fn resolve(&self, req: &HttpRequest) -> std::result::Result<HttpRequest, httpfile::Error> {
+        let dynamic = DynamicVariables::load(&self.model.dir)?;
+        let variables = (
+            self.model.variables.as_slice(),
+            (
+                self.model.environment.as_ref(),
+                (&self.model.responses, dynamic),
+            ),
+        );
+        req.resolve(&variables)
+    }
+
+    fn handle_export_curl(&mut self) {
+        let export = match self.model.selected() {
+            Some(Entry::Request(req)) => self
+                .resolve(req)
+                .map(|req| httpfile::to_curl(&req))
+                .map_err(|err| err.to_string()),
+            Some(Entry::Broken(diagnostic)) => Err(diagnostic.message.clone()),
+            None => return,
+        };
+        self.model.curl_export = Some(export);
+        self.model.state = AppState::ExportingCurl;
+    }
+
+    /// Writes every exchange of the session so far to the HAR file.
+    fn handle_export_har(&mut self) {
+        let path = &self.model.har_path;
+        let har = httpfile::to_har(&self.model.exchanges);
+        let export = match fs::write(path, har) {
+            Ok(()) => Ok(format!(
+                "Saved {} requests to {}",
+                self.model.exchanges.len(),
+                path.display()
+            )),
+            Err(err) => Err(format!("couldn't write {}: {}", path.display(), err)),
+        };
+        self.model.har_export = Some(export);
+        self.model.state = AppState::ExportingHar;
+    }
+
     async fn on_tick(&mut self) -> Result<()> {
         Ok(())
     }
 
-    async fn on_io(&mut self, msg: String) -> Result<()> {
-        self.model.resp = Some(msg);
+    async fn on_io(&mut self, msg: IoMessage) -> Result<()> {
+        if let (Some(name), Ok(response)) = (msg.name, &msg.resp) {
+            self.model.responses.insert(name, response.clone());
+        }
+        self.model.exchanges.push(Exchange {
+            started: msg.started,
+            request: msg.request,
+            response: msg.resp.clone(),
+            timings: msg.timings,
+        });
+        self.model.resp = Some(msg.resp);
         Ok(())
     }
 }
+
+/// Sends `req`, `timings` also covers requests that fail.
+async fn execute(
+    client: reqwest::Client,
+    req: reqwest::Request,
+    timings: &mut Timings,
+) -> reqwest::Result<Response> {
+    let start = Instant::now();
+    let resp = client.execute(req).await;
+    timings.wait = start.elapsed();
+    let resp = resp?;
+    let headers_received = Instant::now();
+    let version = HttpVersion::from_reqwest(resp.version()).unwrap_or_default();
+    let status = resp.status().as_u16();
+    let headers = resp
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            (name.to_string(), value)
+        })
+        .collect();
+    let body = resp.text().await;
+    timings.receive = headers_received.elapsed();
+
+    Ok(Response {
+        version,
+        status,
+        headers,
+        body: body?,
+    })
+}
+
+/// Explains failures to agree on the requested version, hyper only says it's unsupported.
+fn send_error(err: reqwest::Error, version: HttpVersion) -> String {
+    if unsupported_version(&err) {
+        let host = err.url().and_then(|url| url.host_str()).unwrap_or("server");
+        return format!("{} couldn't be negotiated with {}", version, host);
+    }
+
+    err.to_string()
+}
+
+/// Whether hyper refused the version of the request or of the connection it got, the only
+/// user errors without a cause that it returns for requests built by reqwest.
+fn unsupported_version(err: &reqwest::Error) -> bool {
+    let mut source = err.source();
+    while let Some(e) = source {
+        if let Some(e) = e.downcast_ref::<hyper::Error>() {
+            return e.is_user() && e.source().is_none();
+        }
+        source = e.source();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn client_key_protocol() {
+        let input = "GET http://example.com/ HTTP/2
+###
+GET https://example.com/ HTTP/2.0
+###
+GET HTTP://example.com/ HTTP/2
+# @no-redirect
+# @connection-timeout 3
+###
+GET http://example.com/ HTTP/1.0
+";
+        let document = httpfile::parse(io::Cursor::new(input)).unwrap();
+
+        let keys: Vec<_> = document.requests.iter().map(ClientKey::of).collect();
+
+        let protocols: Vec<_> = keys.iter().map(|key| key.protocol).collect();
+        assert_eq!(
+            protocols,
+            [
+                Protocol::H2c,
+                Protocol::Http2,
+                Protocol::H2c,
+                Protocol::Http1
+            ]
+        );
+        assert!(keys[0].redirect && keys[0].cookies);
+        assert!(!keys[2].redirect);
+        assert_eq!(keys[2].connect_timeout, Some(Duration::from_secs(3)));
+    }
+
+    #[tokio::test]
+    async fn send_error_version() {
+        let client = reqwest::Client::new();
+        let req = client
+            .get("http://127.0.0.1:9/")
+            .version(reqwest::Version::HTTP_09)
+            .build()
+            .unwrap();
+
+        let err = execute(client.clone(), req, &mut Timings::default())
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            send_error(err, HttpVersion::Http09),
+            "HTTP/0.9 couldn't be negotiated with 127.0.0.1"
+        );
+
+        let req = client.get("http://[::1]:0/").build().unwrap();
+        let err = execute(client, req, &mut Timings::default())
+            .await
+            .unwrap_err();
+
+        assert!(!send_error(err, HttpVersion::Http11).contains("negotiated"));
+    }
+}
@@ -1,28 +1,121 @@
-use httpfile::HttpRequest;
+use std::path::{Path, PathBuf};
+
+use httpfile::{
+    Diagnostic, Document, Environment, Environments, Exchange, HttpRequest, Language, Response,
+    Responses, Severity, Span, Variable,
+};
 
 use crate::widgets::{SpinnerState, StatefulList};
 
 pub struct Model {
     pub state: AppState,
     pub spinner_state: SpinnerState,
-    pub items: StatefulList<HttpRequest>,
+    pub items: StatefulList<Entry>,
+    /// The .http file and its text, for pointing at diagnostics.
+    pub path: PathBuf,
+    pub source: String,
+    /// Directory of the .http file, `.env` is read from there.
+    pub dir: PathBuf,
+    pub variables: Vec<Variable>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub environments: Environments,
+    pub environment: Option<Environment>,
+    pub env_list: StatefulList<String>,
     pub scroll: Scroll,
+    /// Command typed or pasted into the curl import popup.
+    pub curl_input: String,
+    pub curl_error: Option<String>,
+    /// Selected request as a curl command, or why it couldn't be resolved.
+    pub curl_export: Option<Result<String, String>>,
+    pub lang_list: StatefulList<Language>,
+    /// Selected request as code in the chosen language, or why it couldn't be resolved.
+    pub code: Option<Result<String, String>>,
+    /// Where `h` saves the session as HAR.
+    pub har_path: PathBuf,
+    /// Outcome of the last HAR save.
+    pub har_export: Option<Result<String, String>>,
 
-    pub resp: Option<String>,
+    pub resp: Option<Result<Response, String>>,
+    pub responses: Responses,
     pub request: Option<HttpRequest>,
+    /// Requests sent in this session with their responses.
+    pub exchanges: Vec<Exchange>,
 }
 
 impl Model {
-    pub fn new(items: Vec<HttpRequest>) -> Self {
+    pub fn new(
+        document: Document,
+        source: String,
+        path: PathBuf,
+        environments: Environments,
+        env: Option<String>,
+        har_path: PathBuf,
+    ) -> Self {
+        let environment = env.and_then(|name| environments.get(&name));
+        let env_list = environments.names().map(String::from).collect();
+        let dir = path.parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+
+        let mut items: Vec<Entry> = document
+            .requests
+            .into_iter()
+            .map(|req| Entry::Request(Box::new(req)))
+            .collect();
+        items.extend(
+            document
+                .diagnostics
+                .iter()
+                .filter(|d| d.severity == Severity::Error)
+                .cloned()
+                .map(Entry::Broken),
+        );
+        items.sort_by_key(|entry| entry.span().start);
+
         Self {
             scroll: Scroll { x: 0, y: 0 },
+            curl_input: String::new(),
+            curl_error: None,
+            curl_export: None,
+            lang_list: StatefulList::with_items(Language::ALL.to_vec()),
+            code: None,
+            har_path,
+            har_export: None,
             request: None,
             resp: None,
+            responses: Responses::default(),
+            exchanges: Vec::new(),
             items: StatefulList::with_items(items),
+            path,
+            source,
+            dir,
+            variables: document.variables,
+            diagnostics: document.diagnostics,
+            environments,
+            environment,
+            env_list: StatefulList::with_items(env_list),
             state: AppState::ShowingList,
             spinner_state: SpinnerState::default(),
         }
     }
+
+    pub fn selected(&self) -> Option<&Entry> {
+        let i = self.items.state.selected()?;
+        self.items.items.get(i)
+    }
+}
+
+/// Row of the request list, requests that didn't parse keep their place with their error.
+pub enum Entry {
+    Request(Box<HttpRequest>),
+    Broken(Diagnostic),
+}
+
+impl Entry {
+    pub fn span(&self) -> Span {
+        match self {
+            Entry::Request(req) => req.span,
+            Entry::Broken(diagnostic) => diagnostic.span,
+        }
+    }
 }
 
 pub struct Scroll {
@@ -37,12 +130,12 @@ impl Scroll {
 
     pub fn scroll(&mut self, x: i16, y: i16) {
         self.x = if x.is_negative() {
-            self.x.saturating_sub(x.abs() as u16)
+            self.x.saturating_sub(x.unsigned_abs())
         } else {
             self.x.saturating_add(x as u16)
         };
         self.y = if y.is_negative() {
-            self.y.saturating_sub(y.abs() as u16)
+            self.y.saturating_sub(y.unsigned_abs())
         } else {
             self.y.saturating_add(y as u16)
         };
@@ -53,4 +146,52 @@ impl Scroll {
 pub enum AppState {
     ShowingList,
     DoingRequest,
+    SelectingEnv,
+    ImportingCurl,
+    ExportingCurl,
+    ExportingHar,
+    SelectingLanguage,
+    ShowingCode,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn broken_requests_keep_their_place() {
+        let source = "GET https://example.com/1
+bad header
+###
+# @no-redirects
+GET https://example.com/2
+###
+POST
+";
+        let document = httpfile::parse_recovering(io::Cursor::new(source)).unwrap();
+
+        let model = Model::new(
+            document,
+            source.to_string(),
+            PathBuf::from("dir/api.http"),
+            Environments::default(),
+            None,
+            PathBuf::from("dir/api.har"),
+        );
+
+        let entries: Vec<_> = model
+            .items
+            .items
+            .iter()
+            .map(|entry| match entry {
+                Entry::Request(req) => req.url.to_string(),
+                Entry::Broken(diagnostic) => format!("line {}", diagnostic.span.line),
+            })
+            .collect();
+        assert_eq!(entries, ["line 2", "https://example.com/2", "line 7"]);
+        assert_eq!(model.diagnostics.len(), 3);
+        assert_eq!(model.dir, Path::new("dir"));
+    }
 }
@@ -1,15 +1,19 @@
 use tui::{
     backend::Backend,
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::{Span, Spans},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame,
 };
 
-use super::{AppState, Model};
+use httpfile::{Diagnostic, Severity};
+
+use super::{AppState, Entry, Model};
+use crate::report;
 use crate::widgets::Spinner;
 
+#[derive(Default)]
 pub struct View {}
 
 impl View {
@@ -21,6 +25,30 @@ impl View {
         match model.state {
             AppState::ShowingList => self.showing_list_ui(f, model),
             AppState::DoingRequest => self.doing_request_ui(f, model),
+            AppState::SelectingEnv => {
+                self.showing_list_ui(f, model);
+                self.selecting_env_ui(f, model);
+            }
+            AppState::ImportingCurl => {
+                self.showing_list_ui(f, model);
+                self.importing_curl_ui(f, model);
+            }
+            AppState::ExportingCurl => {
+                self.showing_list_ui(f, model);
+                self.exporting_curl_ui(f, model);
+            }
+            AppState::ExportingHar => {
+                self.showing_list_ui(f, model);
+                self.exporting_har_ui(f, model);
+            }
+            AppState::SelectingLanguage => {
+                self.showing_list_ui(f, model);
+                self.selecting_language_ui(f, model);
+            }
+            AppState::ShowingCode => {
+                self.showing_list_ui(f, model);
+                self.showing_code_ui(f, model);
+            }
         }
     }
 
@@ -29,30 +57,44 @@ impl View {
             .items
             .items
             .iter()
-            .map(|req| {
-                let main_line = format!("{} {} {}", req.method, req.url, req.version);
+            .map(|entry| {
+                let req = match entry {
+                    Entry::Request(req) => req,
+                    Entry::Broken(diagnostic) => return broken_item(&model.source, diagnostic),
+                };
+                let main_line = format!("{} {} {}", req.method_text(), req.url, req.version_text());
 
-                let comment: Spans =
-                    Span::styled(req.comment.clone(), Style::default().fg(Color::Yellow)).into();
-                let mut lines = vec![comment, Spans::from(main_line)];
+                let mut lines: Vec<Spans> = req
+                    .comment
+                    .split('\n')
+                    .map(|line| Span::styled(line, Style::default().fg(Color::Yellow)).into())
+                    .collect();
+                if let Some(name) = &req.name {
+                    lines.push(
+                        Span::styled(format!("@{}", name), Style::default().fg(Color::Cyan)).into(),
+                    );
+                }
+                lines.push(Spans::from(main_line));
                 lines.extend(
                     req.headers
                         .iter()
                         .map(|h| format!("{}: {}", h.name, h.value))
                         .map(Spans::from),
                 );
-                lines.push(Spans::from(req.body.clone()));
+                let body = req.body.to_string();
+                lines.extend(body.lines().map(|line| Spans::from(line.to_string())));
                 ListItem::new(lines).style(Style::default())
             })
             .collect();
 
+        let title = match &model.environment {
+            Some(env) => format!("HTTP requests [{}]", env.name),
+            None => String::from("HTTP requests"),
+        };
+
         // Create a List from all list items and highlight the currently selected one
         let items = List::new(items)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("HTTP requests"),
-            )
+            .block(Block::default().borders(Borders::ALL).title(title))
             .highlight_style(
                 Style::default()
                     .bg(Color::LightGreen)
@@ -60,8 +102,29 @@ impl View {
             )
             .highlight_symbol(">> ");
 
+        let area = if model.diagnostics.is_empty() {
+            f.size()
+        } else {
+            let lines = diagnostic_lines(model);
+            let height = lines.len().min(12) as u16 + 2;
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(height)].as_ref())
+                .split(f.size());
+            self.diagnostics_ui(f, lines, chunks[1]);
+            chunks[0]
+        };
+
         // We can now render the item list
-        f.render_stateful_widget(items, f.size(), &mut model.items.state);
+        f.render_stateful_widget(items, area, &mut model.items.state);
+    }
+
+    fn diagnostics_ui<B: Backend>(&mut self, f: &mut Frame<B>, lines: Vec<Spans>, area: Rect) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Diagnostics")
+            .border_style(Style::default().fg(Color::Red));
+        f.render_widget(Paragraph::new(lines).block(block), area);
     }
 
     fn doing_request_ui<B: Backend>(&mut self, f: &mut Frame<B>, model: &mut Model) {
@@ -73,6 +136,18 @@ impl View {
         {
             let mut text = vec![Spans::from("")];
             let req = model.request.clone().unwrap();
+            let query = req.query();
+            if !query.is_empty() {
+                let key_style = Style::default().fg(Color::Cyan);
+                text.extend(query.into_iter().map(|(key, value)| {
+                    Spans::from(vec![
+                        Span::styled(key, key_style),
+                        Span::raw(" = "),
+                        Span::raw(value),
+                    ])
+                }));
+                text.push(Spans::from(""));
+            }
             req.headers
                 .iter()
                 .map(|header| Spans::from(format!("{}: {}", header.name, header.value)))
@@ -81,24 +156,38 @@ impl View {
 
             text.extend(
                 req.body
-                    .split("\n")
-                    .map(|line| Spans::from(vec![Span::raw(line), Span::raw("")])),
+                    .to_string()
+                    .split('\n')
+                    .map(|line| Spans::from(vec![Span::raw(line.to_string()), Span::raw("")])),
             );
 
             let request_block = Block::default()
-                .title(format!("{} {} {}", req.method, req.url, req.version))
+                .title(format!(
+                    "{} {} {}",
+                    req.method_text(),
+                    req.url,
+                    req.version_text()
+                ))
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(Color::White));
             let request_part = Paragraph::new(text).block(request_block);
             f.render_widget(request_part, chunks[0]);
         }
         {
+            let title = match &model.resp {
+                Some(Ok(resp)) => format!("Response {} {}", resp.version, resp.status),
+                _ => String::from("Response"),
+            };
             let response_block = Block::default()
-                .title("Response")
+                .title(title)
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(Color::White));
-            if let Some(body) = &model.resp {
-                let response_part = Paragraph::new(body.to_string())
+            if let Some(resp) = &model.resp {
+                let text = match resp {
+                    Ok(resp) => Text::raw(resp.body.as_str()),
+                    Err(err) => Text::styled(err.as_str(), Style::default().fg(Color::Red)),
+                };
+                let response_part = Paragraph::new(text)
                     .block(response_block)
                     .scroll(model.scroll.to_tuple());
                 f.render_widget(response_part, chunks[1]);
@@ -111,4 +200,183 @@ impl View {
             }
         }
     }
+
+    fn selecting_env_ui<B: Backend>(&mut self, f: &mut Frame<B>, model: &mut Model) {
+        let items: Vec<ListItem> = model
+            .env_list
+            .items
+            .iter()
+            .map(|name| ListItem::new(name.as_str()))
+            .collect();
+        let items = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Environment (Backspace: none)"),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+
+        let area = centered_rect(50, 40, f.size());
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(items, area, &mut model.env_list.state);
+    }
+
+    fn importing_curl_ui<B: Backend>(&mut self, f: &mut Frame<B>, model: &Model) {
+        let mut text = Text::raw(model.curl_input.as_str());
+        if let Some(err) = &model.curl_error {
+            text.extend(Text::styled(
+                format!("\n{}", err),
+                Style::default().fg(Color::Red),
+            ));
+        }
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Paste a curl command (Enter: import, Esc: cancel)");
+        let paragraph = Paragraph::new(text).block(block).wrap(Wrap { trim: false });
+
+        let area = centered_rect(80, 60, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
+
+    fn exporting_curl_ui<B: Backend>(&mut self, f: &mut Frame<B>, model: &Model) {
+        let text = match &model.curl_export {
+            Some(Ok(curl)) => Text::raw(curl.as_str()),
+            Some(Err(err)) => Text::styled(err.as_str(), Style::default().fg(Color::Red)),
+            None => Text::default(),
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("curl (any key: close)");
+        let paragraph = Paragraph::new(text).block(block).wrap(Wrap { trim: false });
+
+        let area = centered_rect(80, 60, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
+
+    fn selecting_language_ui<B: Backend>(&mut self, f: &mut Frame<B>, model: &mut Model) {
+        let items: Vec<ListItem> = model
+            .lang_list
+            .items
+            .iter()
+            .map(|lang| ListItem::new(lang.as_str()))
+            .collect();
+        let items = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Generate code"),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+
+        let area = centered_rect(30, 30, f.size());
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(items, area, &mut model.lang_list.state);
+    }
+
+    fn showing_code_ui<B: Backend>(&mut self, f: &mut Frame<B>, model: &Model) {
+        let text = match &model.code {
+            Some(Ok(code)) => Text::raw(code.as_str()),
+            Some(Err(err)) => Text::styled(err.as_str(), Style::default().fg(Color::Red)),
+            None => Text::default(),
+        };
+        let lang = model
+            .lang_list
+            .state
+            .selected()
+            .and_then(|i| model.lang_list.items.get(i));
+        let title = match lang {
+            Some(lang) => format!("{} (any key: close)", lang),
+            None => String::from("Code (any key: close)"),
+        };
+        let block = Block::default().borders(Borders::ALL).title(title);
+        let paragraph = Paragraph::new(text).block(block);
+
+        let area = centered_rect(80, 80, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
+
+    fn exporting_har_ui<B: Backend>(&mut self, f: &mut Frame<B>, model: &Model) {
+        let text = match &model.har_export {
+            Some(Ok(message)) => Text::raw(message.as_str()),
+            Some(Err(err)) => Text::styled(err.as_str(), Style::default().fg(Color::Red)),
+            None => Text::default(),
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("HAR (any key: close)");
+        let paragraph = Paragraph::new(text).block(block).wrap(Wrap { trim: false });
+
+        let area = centered_rect(60, 20, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
+}
+
+/// Diagnostics with the source lines they point at, the first line colored by severity.
+fn diagnostic_lines(model: &Model) -> Vec<Spans<'static>> {
+    let mut lines = Vec::new();
+    for d in &model.diagnostics {
+        let color = match d.severity {
+            Severity::Error => Color::Red,
+            Severity::Warning => Color::Yellow,
+        };
+        let text = report::render_diagnostic(&model.path, &model.source, d);
+        let mut text = text.lines().map(str::to_string);
+        lines.extend(
+            text.next()
+                .map(|line| Span::styled(line, Style::default().fg(color))),
+        );
+        lines.extend(text.map(Span::raw));
+    }
+    lines.into_iter().map(Spans::from).collect()
+}
+
+/// Source lines of a request that didn't parse, marked with its error.
+fn broken_item<'a>(source: &'a str, diagnostic: &Diagnostic) -> ListItem<'a> {
+    let style = Style::default().fg(Color::Red);
+    let text = source.get(diagnostic.span.start..diagnostic.span.end);
+    let mut lines = vec![Spans::from(Span::styled(
+        format!(
+            "{}:{} {}: {}",
+            diagnostic.span.line, diagnostic.span.column, diagnostic.severity, diagnostic.message
+        ),
+        style.add_modifier(Modifier::BOLD),
+    ))];
+    lines.extend(
+        text.unwrap_or_default()
+            .lines()
+            .map(|line| Spans::from(Span::styled(line, style))),
+    );
+    ListItem::new(lines)
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_y) / 2),
+                Constraint::Percentage(percent_y),
+                Constraint::Percentage((100 - percent_y) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_x) / 2),
+                Constraint::Percentage(percent_x),
+                Constraint::Percentage((100 - percent_x) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(vertical[1])[1]
 }
@@ -0,0 +1,150 @@
+use std::path::Path;
+
+use httpfile::{Diagnostic, Error, Lint};
+
+/// Formats `err` for the terminal, pointing into `source` when the position is known.
+pub fn render(path: &Path, source: &str, err: &Error) -> String {
+    match err {
+        Error::Syntax {
+            line,
+            column,
+            source: err,
+        } => annotate("error", &err.to_string(), path, source, *line, *column),
+        err => format!("error: {}\n", err),
+    }
+}
+
+/// Formats `lint` like a compiler warning, with its rule in brackets.
+pub fn render_lint(path: &Path, source: &str, lint: &Lint) -> String {
+    let level = format!("{}[{}]", lint.severity, lint.rule);
+    let (line, column) = (lint.span.line, lint.span.column);
+    annotate(&level, &lint.message, path, source, line, column)
+}
+
+/// Formats a problem the recovering parser found.
+pub fn render_diagnostic(path: &Path, source: &str, diagnostic: &Diagnostic) -> String {
+    let level = diagnostic.severity.to_string();
+    let (line, column) = (diagnostic.span.line, diagnostic.span.column);
+    annotate(&level, &diagnostic.message, path, source, line, column)
+}
+
+fn annotate(
+    level: &str,
+    message: &str,
+    path: &Path,
+    source: &str,
+    line: usize,
+    column: usize,
+) -> String {
+    let text = source.lines().nth(line - 1).unwrap_or_default();
+    let gutter = line.to_string().len();
+    let marker: String = text
+        .chars()
+        .take(column - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    format!(
+        "{level}: {message}\n{:gutter$}--> {}:{line}:{column}\n{:gutter$} |\n{line} | {text}\n{:gutter$} | {marker}^\n",
+        "",
+        path.display(),
+        "",
+        "",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use httpfile::{Rule, Severity, Span};
+
+    use super::*;
+
+    fn syntax(line: usize, column: usize) -> Error {
+        Error::Syntax {
+            line,
+            column,
+            source: Box::new(Error::InvalidURL),
+        }
+    }
+
+    #[test]
+    fn render_points_at_column() {
+        let path = Path::new("api.http");
+        let source = "GET https://example.com/\n\tX-A: 1\nGET https://ex ample.com/";
+
+        assert_eq!(
+            render(path, source, &syntax(3, 15)),
+            "error: invalid URL
+ --> api.http:3:15
+  |
+3 | GET https://ex ample.com/
+  |               ^
+"
+        );
+        assert_eq!(
+            render(path, source, &syntax(2, 2)),
+            "error: invalid URL
+ --> api.http:2:2
+  |
+2 | \tX-A: 1
+  | \t^
+"
+        );
+        assert_eq!(
+            render(path, source, &Error::NoUrl),
+            "error: couldn't parse http url\n"
+        );
+    }
+
+    #[test]
+    fn render_counts_characters() {
+        let path = Path::new("api.http");
+        let source = "# café ☕\nGET https://example.com/ñ|\n";
+
+        assert_eq!(
+            render(path, source, &syntax(2, 26)),
+            "error: invalid URL
+ --> api.http:2:26
+  |
+2 | GET https://example.com/ñ|
+  |                          ^
+"
+        );
+    }
+
+    #[test]
+    fn render_lint_past_last_line() {
+        let path = Path::new("dir/api.http");
+        let source = (1..=10).map(|i| format!("# {}\n", i)).collect::<String>();
+        let lint = Lint {
+            rule: Rule::UndefinedVariable,
+            severity: Severity::Warning,
+            span: Span {
+                line: 10,
+                column: 4,
+                ..Span::default()
+            },
+            message: "`host` is not defined".to_string(),
+        };
+
+        assert_eq!(
+            render_lint(path, &source, &lint),
+            "warning[undefined-variable]: `host` is not defined
+  --> dir/api.http:10:4
+   |
+10 | # 10
+   |    ^
+"
+        );
+
+        let lint = Lint {
+            span: Span {
+                line: 11,
+                column: 1,
+                ..Span::default()
+            },
+            ..lint
+        };
+        assert!(render_lint(path, &source, &lint).ends_with("\n11 | \n   | ^\n"));
+    }
+}
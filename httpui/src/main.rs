@@ -1,33 +1,44 @@
-use std::{
-    error::Error,
-    fs::File,
-    io::{self, BufReader},
-    path::Path,
-    time::Duration,
-};
+use std::{error::Error, fs, io, path::Path, process, time::Duration};
 
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use httpfile::Environments;
 use httpui::mvc::Controller;
+use httpui::report;
 use tui::{backend::CrosstermBackend, Terminal};
 
 mod args;
+mod commands;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     let args = args::parse();
-    let https = read_http_file(&args.path)?
-        .into_iter()
-        .map(|mut req| {
-            req.body = serde_json::from_str::<serde_json::Value>(&req.body)
-                .and_then(|obj| serde_json::to_string_pretty(&obj))
-                .unwrap_or(req.body);
-            req
-        })
-        .collect();
+    if let Some(command) = args.command {
+        return commands::run(command);
+    }
+    let path = args.path.ok_or("missing path to a .http file")?;
+    let source = fs::read_to_string(&path)?;
+    let mut document = match httpfile::parse_recovering(io::Cursor::new(&source)) {
+        Ok(document) => document,
+        Err(err) => {
+            eprint!("{}", report::render(&path, &source, &err));
+            process::exit(1);
+        }
+    };
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    document.rebase(dir);
+    let environments = Environments::load(dir)?;
+    if let Some(name) = &args.env {
+        if environments.get(name).is_none() {
+            return Err(format!("environment `{}` is not defined", name).into());
+        }
+    }
+
+    let har_path = args.har.unwrap_or_else(|| path.with_extension("har"));
+    let app = Controller::new(document, source, path, environments, args.env, har_path)?;
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -35,7 +46,6 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
-    let app = Controller::new(https);
     let tick_rate = Duration::from_millis(200);
 
     let res = app.run(&mut terminal, tick_rate).await;
@@ -55,8 +65,3 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
-
-fn read_http_file(path: &Path) -> Result<Vec<httpfile::HttpRequest>, Box<dyn Error>> {
-    let file = File::open(path)?;
-    httpfile::parse(BufReader::new(file)).map_err(Into::into)
-}